@@ -0,0 +1,120 @@
+//! Débit de hachage sur des fichiers temporaires. `cargo bench` lance tous les groupes,
+//! `cargo bench -- <nom>` seulement ceux dont le nom contient `<nom>`.
+
+use rayon::prelude::*;
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    time::{Duration, Instant},
+};
+use zhashgen::{HashAlgo, HashConfig, hash_file};
+
+// Passes par mesure ; la plus rapide est retenue (cache disque chaud)
+const ROUNDS: usize = 5;
+
+fn main() {
+    // Cargo ajoute --bench : seuls les arguments libres servent de filtre
+    let filters: Vec<String> = std::env::args()
+        .skip(1)
+        .filter(|arg| !arg.starts_with("--"))
+        .collect();
+    let selected = |group: &str| filters.is_empty() || filters.iter().any(|f| group.contains(f));
+
+    let dir = std::env::temp_dir().join(format!("zhashgen-bench-{}", std::process::id()));
+    if selected("small-files") {
+        small_files(&dir.join("small"));
+    }
+    if selected("large-files") {
+        large_files(&dir.join("large"));
+    }
+    if selected("buffer-size") {
+        buffer_sizes(&dir.join("buffer"));
+    }
+    let _ = fs::remove_dir_all(&dir);
+}
+
+// Beaucoup de petits fichiers, chargés en entier : Vec par fichier ou projection (--mmap)
+fn small_files(dir: &Path) {
+    for (count, kib) in [(20_000, 4), (200, 1024)] {
+        let files = write_files(&dir.join(format!("{kib}")), count, kib * 1024);
+        for mmap in [false, true] {
+            let config = HashConfig {
+                algo: HashAlgo::Xxh3,
+                mmap,
+                ..HashConfig::default()
+            };
+            let name = format!("small-files {count} x {kib} KiB, mmap={mmap}");
+            measure(&name, &files, &config);
+        }
+    }
+}
+
+// Beaucoup de gros fichiers en streaming : le tampon de lecture est celui du thread,
+// réutilisé d'un fichier à l'autre
+fn large_files(dir: &Path) {
+    let files = write_files(dir, 16, 64 * 1024 * 1024);
+    let config = HashConfig {
+        algo: HashAlgo::Xxh3,
+        full_load_limit: 0,
+        ..HashConfig::default()
+    };
+    measure("large-files 16 x 64 MiB, streaming", &files, &config);
+}
+
+// Un seul gros fichier en streaming, selon la taille du tampon (--buffer-size)
+fn buffer_sizes(dir: &Path) {
+    let files = write_files(dir, 1, 1024 * 1024 * 1024);
+    for kib in [64, 1024, 16 * 1024] {
+        let config = HashConfig {
+            algo: HashAlgo::Xxh3,
+            full_load_limit: 0,
+            buffer_size: kib * 1024,
+            ..HashConfig::default()
+        };
+        measure(&format!("buffer-size 1 GiB, {kib} KiB"), &files, &config);
+    }
+}
+
+// Fichiers de contenu pseudo-aléatoire (pas de zéros : rien de creux ni de compressible)
+fn write_files(dir: &Path, count: usize, size: usize) -> Vec<PathBuf> {
+    fs::create_dir_all(dir).unwrap();
+    let mut state = 0x9E37_79B9_7F4A_7C15u64;
+    (0..count)
+        .map(|i| {
+            let data: Vec<u8> = (0..size)
+                .map(|_| {
+                    state ^= state << 13;
+                    state ^= state >> 7;
+                    state ^= state << 17;
+                    state as u8
+                })
+                .collect();
+            let path = dir.join(format!("{i:06}"));
+            fs::write(&path, data).unwrap();
+            path
+        })
+        .collect()
+}
+
+// Fichiers hachés en parallèle sur le pool rayon global, comme par zhashgen
+fn measure(name: &str, files: &[PathBuf], config: &HashConfig) {
+    let bytes: u64 = files
+        .iter()
+        .map(|path| fs::metadata(path).unwrap().len())
+        .sum();
+    let best = (0..ROUNDS)
+        .map(|_| {
+            let start = Instant::now();
+            files.par_iter().for_each(|path| {
+                hash_file(path, config).unwrap();
+            });
+            start.elapsed()
+        })
+        .min()
+        .unwrap_or(Duration::ZERO);
+    let mib_per_s = bytes as f64 / (1024.0 * 1024.0) / best.as_secs_f64();
+    println!(
+        "{name:<48} {:>9.1} ms {mib_per_s:>9.0} MiB/s",
+        best.as_secs_f64() * 1000.0
+    );
+}
@@ -0,0 +1,135 @@
+//! Fichier de configuration (newcrc.toml) : valeurs par défaut pour les options courantes.
+//! Une option passée sur la ligne de commande (ou par variable d'environnement) l'emporte
+//! toujours sur le fichier.
+
+use crate::{Args, EXIT_FATAL, OutputFormat, parse_size};
+use clap::{ArgMatches, CommandFactory, FromArgMatches, ValueEnum, parser::ValueSource};
+use serde::Deserialize;
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+use zhashgen::HashAlgo;
+
+// Cherché dans le répertoire courant quand --config n'est pas donné
+const CONFIG_FILE: &str = "newcrc.toml";
+
+// Clés au nom des options longues : algo = "xxh3,sha256", min-size = "10MiB"...
+#[derive(Default, Deserialize)]
+#[serde(default, rename_all = "kebab-case", deny_unknown_fields)]
+struct FileConfig {
+    source: Option<PathBuf>,
+    output_dir: Option<PathBuf>,
+    algo: Option<String>,
+    threads: Option<usize>,
+    format: Option<String>,
+    include: Option<Vec<String>>,
+    exclude: Option<Vec<String>>,
+    ext: Option<Vec<String>>,
+    min_size: Option<String>,
+    max_size: Option<String>,
+    max_depth: Option<usize>,
+    skip_hidden: Option<bool>,
+    respect_gitignore: Option<bool>,
+}
+
+// Ligne de commande complétée par --config ou ./newcrc.toml s'il existe
+pub fn parse_args() -> io::Result<Args> {
+    let matches = Args::command()
+        .try_get_matches()
+        .unwrap_or_else(|e| exit_usage(e));
+    let mut args = Args::from_arg_matches(&matches).unwrap_or_else(|e| exit_usage(e));
+    let path = match &args.config {
+        Some(path) => Some(path.clone()),
+        None => Path::new(CONFIG_FILE)
+            .is_file()
+            .then(|| PathBuf::from(CONFIG_FILE)),
+    };
+    if let Some(path) = path {
+        load(&path)?.apply(&mut args, &matches, &path)?;
+    }
+    Ok(args)
+}
+
+// Option invalide : EXIT_FATAL plutôt que le code 2 de clap, réservé aux différences
+// trouvées par --check ; --help et --version sortent normalement
+fn exit_usage(e: clap::Error) -> ! {
+    if e.use_stderr() {
+        let _ = e.print();
+        std::process::exit(EXIT_FATAL);
+    }
+    e.exit()
+}
+
+fn load(path: &Path) -> io::Result<FileConfig> {
+    let content = fs::read_to_string(path)?;
+    toml::from_str(&content).map_err(|e| invalid(path, e))
+}
+
+fn invalid(path: &Path, e: impl std::fmt::Display) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!("Configuration {} : {e}", path.display()),
+    )
+}
+
+impl FileConfig {
+    fn apply(self, args: &mut Args, matches: &ArgMatches, path: &Path) -> io::Result<()> {
+        // Seules les options restées à leur valeur par défaut sont remplacées
+        let unset = |id: &str| {
+            matches!(
+                matches.value_source(id),
+                None | Some(ValueSource::DefaultValue)
+            )
+        };
+        let size = |value: &str| parse_size(value).map_err(|e| invalid(path, e));
+
+        if let Some(source) = self.source.filter(|_| unset("source")) {
+            args.source = source;
+        }
+        if let Some(output_dir) = self.output_dir.filter(|_| unset("output_dir")) {
+            args.output_dir = output_dir;
+        }
+        if let Some(algo) = self.algo.filter(|_| unset("algo")) {
+            args.algo = algo
+                .split(',')
+                .map(|name| HashAlgo::from_str(name.trim(), false))
+                .collect::<Result<_, _>>()
+                .map_err(|e| invalid(path, e))?;
+        }
+        if let Some(threads) = self.threads.filter(|_| unset("threads")) {
+            args.threads = threads;
+        }
+        if let Some(format) = self.format.filter(|_| unset("format")) {
+            args.format = OutputFormat::from_str(&format, false).map_err(|e| invalid(path, e))?;
+        }
+        if let Some(include) = self.include.filter(|_| unset("include")) {
+            args.include = include;
+        }
+        if let Some(exclude) = self.exclude.filter(|_| unset("exclude")) {
+            args.exclude = exclude;
+        }
+        if let Some(ext) = self.ext.filter(|_| unset("ext")) {
+            args.ext = ext;
+        }
+        if let Some(min_size) = self.min_size.filter(|_| unset("min_size")) {
+            args.min_size = Some(size(&min_size)?);
+        }
+        if let Some(max_size) = self.max_size.filter(|_| unset("max_size")) {
+            args.max_size = Some(size(&max_size)?);
+        }
+        if let Some(max_depth) = self.max_depth.filter(|_| unset("max_depth")) {
+            args.max_depth = Some(max_depth);
+        }
+        if let Some(skip_hidden) = self.skip_hidden.filter(|_| unset("skip_hidden")) {
+            args.skip_hidden = skip_hidden;
+        }
+        if let Some(respect) = self
+            .respect_gitignore
+            .filter(|_| unset("respect_gitignore"))
+        {
+            args.respect_gitignore = respect;
+        }
+        Ok(())
+    }
+}
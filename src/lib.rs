@@ -0,0 +1,1777 @@
+//! Cœur de hachage de NewCrc : choix de l'algorithme, lecture complète en mémoire
+//! pour les petits fichiers et streaming par blocs pour les gros.
+
+use cityhash::city_hash_128;
+use clap::ValueEnum;
+use crc::{CRC_64_XZ, Crc};
+use crc32c::crc32c_append; // Pour CRC32C (Castagnoli)
+use crc32fast::Hasher as Crc32Hasher;
+use fnv::FnvHasher;
+use memmap2::Mmap;
+use murmur3::Murmur3;
+use rayon::prelude::*;
+use std::{
+    cell::RefCell,
+    fs::{self, File},
+    hash::Hasher,
+    io::{self, Read},
+    path::Path,
+    sync::{Condvar, Mutex, mpsc},
+    thread,
+    time::{Duration, SystemTime},
+};
+use xxhash_rust::xxh3::Xxh3;
+
+// Imports pour les nouveaux algorithmes
+use blake2::{
+    Blake2b512, Blake2bVar, Blake2s256,
+    digest::{Update, VariableOutput},
+};
+use blake3::Hasher as Blake3Hasher;
+use hmac::{Hmac, Mac, SimpleHmac, digest::KeyInit};
+use md5::Md5;
+use sha1::Sha1;
+use sha2::{Digest, Sha256, Sha384, Sha512, Sha512_256};
+use sha3::{Sha3_256, Sha3_512};
+
+mod murmur3;
+
+/// Taille par défaut du tampon pour le mode streaming (1 MiB)
+pub const DEFAULT_BUFFER_SIZE: usize = 1024 * 1024;
+/// Longueur maximale (et par défaut) d'un digest BLAKE2b, en octets
+pub const BLAKE2B_MAX_LEN: usize = 64;
+/// Taille au-delà de laquelle un fichier est lu en streaming plutôt que chargé en entier
+pub const DEFAULT_FULL_LOAD_LIMIT: u64 = 200 * 1024 * 1024;
+// Nouvelles tentatives sur erreur transitoire (Interrupted...), délai doublé à chaque fois
+const TRANSIENT_RETRIES: u32 = 3;
+const RETRY_BASE_DELAY: Duration = Duration::from_millis(10);
+// Blocs lus d'avance par fichier en mode deux étages (hash_file_pipelined)
+const PIPELINE_DEPTH: usize = 4;
+// Au-delà de cette taille, BLAKE3 répartit le hachage d'un tampon sur le pool rayon
+const BLAKE3_RAYON_THRESHOLD: usize = 128 * 1024;
+// Taille des blocs CRC32 hachés en parallèle puis recombinés (intra_file_parallel)
+const PARALLEL_CHUNK_SIZE: usize = 16 * 1024 * 1024;
+
+thread_local! {
+    // Tampon de lecture du mode streaming, alloué une fois par thread rayon
+    // puis réutilisé d'un fichier à l'autre (redimensionné selon HashConfig::buffer_size)
+    static STREAM_BUFFER: RefCell<Vec<u8>> = const { RefCell::new(Vec::new()) };
+}
+
+// CRC64 ECMA-182 (variante xz : réfléchie, init et XOR final à 0xFFFF...)
+static CRC64_XZ: Crc<u64> = Crc::<u64>::new(&CRC_64_XZ);
+
+/// Algorithmes de hachage supportés
+#[derive(Copy, Clone, PartialEq, Eq, ValueEnum)]
+pub enum HashAlgo {
+    // Hashes légers et rapides (non-cryptographiques)
+    // CRC-16 : images de firmware, trames série ; variante choisie par HashConfig::crc16
+    Crc16,
+    Crc32,
+    Crc32c,
+    Crc64,
+    Adler32,
+    City128,
+    Xxh3,
+    Xxh3_128,
+    // FNV-1a et MurmurHash3 sont faits pour les tables de hachage : aucune résistance aux
+    // collisions fabriquées, et FNV-1a 64 bits en produit vite sur de gros volumes
+    Fnv1a,
+    Murmur3,
+    // Hashes cryptographiques (plus lents, plus sécurisés)
+    // MD5 est cassé : uniquement pour la compatibilité avec d'anciens manifestes
+    Md5,
+    Sha1,
+    Sha256,
+    Sha512,
+    // Variantes FIPS 180-4 : valeurs initiales propres, pas un simple SHA-512 tronqué
+    Sha384,
+    Sha512_256,
+    Sha3_256,
+    Sha3_512,
+    Blake2b,
+    Blake2s,
+    Blake3,
+}
+
+impl HashAlgo {
+    /// Longueur du digest en caractères hexadécimaux (BLAKE2b : longueur par défaut, voir
+    /// [`HashConfig::blake2b_len`])
+    pub fn hex_len(self) -> usize {
+        match self {
+            HashAlgo::Crc16 => 4,
+            HashAlgo::Crc32 | HashAlgo::Crc32c | HashAlgo::Adler32 => 8,
+            HashAlgo::Crc64 | HashAlgo::Xxh3 | HashAlgo::Fnv1a => 16,
+            HashAlgo::City128 | HashAlgo::Xxh3_128 | HashAlgo::Murmur3 | HashAlgo::Md5 => 32,
+            HashAlgo::Sha1 => 40,
+            HashAlgo::Sha256
+            | HashAlgo::Sha512_256
+            | HashAlgo::Sha3_256
+            | HashAlgo::Blake2s
+            | HashAlgo::Blake3 => 64,
+            HashAlgo::Sha384 => 96,
+            HashAlgo::Sha512 | HashAlgo::Sha3_512 | HashAlgo::Blake2b => 128,
+        }
+    }
+
+    /// Extension des manifestes générés en mode interactif (CRC.<ext>)
+    pub fn extension(self) -> &'static str {
+        match self {
+            HashAlgo::Crc16 => "crc16",
+            HashAlgo::Crc32 => "crc32",
+            HashAlgo::Crc32c => "crc32c",
+            HashAlgo::Crc64 => "crc64",
+            HashAlgo::Adler32 => "adler32",
+            HashAlgo::City128 => "city128",
+            HashAlgo::Xxh3 => "xxhash3",
+            HashAlgo::Xxh3_128 => "xxhash3-128",
+            HashAlgo::Fnv1a => "fnv1a",
+            HashAlgo::Murmur3 => "murmur3",
+            HashAlgo::Md5 => "md5",
+            HashAlgo::Sha1 => "sha1",
+            HashAlgo::Sha256 => "sha256",
+            HashAlgo::Sha512 => "sha512",
+            HashAlgo::Sha384 => "sha384",
+            HashAlgo::Sha512_256 => "sha512-256",
+            HashAlgo::Sha3_256 => "sha3-256",
+            HashAlgo::Sha3_512 => "sha3-512",
+            HashAlgo::Blake2b => "blake2b",
+            HashAlgo::Blake2s => "blake2s",
+            HashAlgo::Blake3 => "blake3",
+        }
+    }
+
+    /// Nom de l'algorithme dans les lignes étiquetées façon BSD (`SHA256 (chemin) = digest`)
+    pub fn tag(self) -> &'static str {
+        match self {
+            HashAlgo::Crc16 => "CRC16",
+            HashAlgo::Crc32 => "CRC32",
+            HashAlgo::Crc32c => "CRC32C",
+            HashAlgo::Crc64 => "CRC64",
+            HashAlgo::Adler32 => "ADLER32",
+            HashAlgo::City128 => "CITY128",
+            HashAlgo::Xxh3 => "XXH3",
+            HashAlgo::Xxh3_128 => "XXH3-128",
+            HashAlgo::Fnv1a => "FNV1A",
+            HashAlgo::Murmur3 => "MURMUR3",
+            HashAlgo::Md5 => "MD5",
+            HashAlgo::Sha1 => "SHA1",
+            HashAlgo::Sha256 => "SHA256",
+            HashAlgo::Sha512 => "SHA512",
+            HashAlgo::Sha384 => "SHA384",
+            // Étiquette de sha512t256 (FreeBSD)
+            HashAlgo::Sha512_256 => "SHA512t256",
+            HashAlgo::Sha3_256 => "SHA3-256",
+            HashAlgo::Sha3_512 => "SHA3-512",
+            HashAlgo::Blake2b => "BLAKE2B",
+            HashAlgo::Blake2s => "BLAKE2S",
+            HashAlgo::Blake3 => "BLAKE3",
+        }
+    }
+
+    /// Algorithme correspondant à une étiquette BSD, sans tenir compte de la casse
+    /// (coreutils écrit `BLAKE2b`)
+    pub fn from_tag(tag: &str) -> Option<HashAlgo> {
+        HashAlgo::value_variants()
+            .iter()
+            .copied()
+            .find(|algo| algo.tag().eq_ignore_ascii_case(tag))
+    }
+
+    /// Débit indicatif sur un cœur, en MiB/s (x86-64 récent, SIMD et SHA-NI compris). Sert à
+    /// estimer la durée d'une génération, pas à comparer des matériels : voir `--bench`.
+    pub fn nominal_throughput(self) -> u64 {
+        match self {
+            HashAlgo::Xxh3 | HashAlgo::Xxh3_128 => 12_000,
+            HashAlgo::Crc32 | HashAlgo::Crc32c => 8_000,
+            HashAlgo::City128 => 6_000,
+            HashAlgo::Blake3 | HashAlgo::Murmur3 => 4_000,
+            HashAlgo::Adler32 => 3_000,
+            HashAlgo::Crc16 => 500,
+            HashAlgo::Crc64 => 2_000,
+            HashAlgo::Fnv1a | HashAlgo::Sha1 | HashAlgo::Sha256 => 1_000,
+            HashAlgo::Blake2b | HashAlgo::Sha512 | HashAlgo::Sha384 | HashAlgo::Sha512_256 => 800,
+            HashAlgo::Md5 => 600,
+            HashAlgo::Blake2s | HashAlgo::Sha3_256 => 450,
+            HashAlgo::Sha3_512 => 250,
+        }
+    }
+
+    /// Algorithme cryptographique utilisable en HMAC ([`HashConfig::hmac_key`]). BLAKE3 en
+    /// est exclu : son mode à clé impose une clé de 32 octets et n'est pas un HMAC.
+    pub fn supports_hmac(self) -> bool {
+        matches!(
+            self,
+            HashAlgo::Md5
+                | HashAlgo::Sha1
+                | HashAlgo::Sha256
+                | HashAlgo::Sha512
+                | HashAlgo::Sha384
+                | HashAlgo::Sha512_256
+                | HashAlgo::Sha3_256
+                | HashAlgo::Sha3_512
+                | HashAlgo::Blake2b
+                | HashAlgo::Blake2s
+        )
+    }
+}
+
+/// Paramètres de lecture et de hachage d'un fichier
+#[derive(Copy, Clone)]
+pub struct HashConfig {
+    pub algo: HashAlgo,
+    /// Limite au-dessus de laquelle on passe en mode streaming pour économiser la RAM
+    pub full_load_limit: u64,
+    /// Petits fichiers lus par projection mémoire plutôt que copiés dans un Vec
+    pub mmap: bool,
+    /// Gros fichiers découpés en blocs hachés en parallèle (BLAKE3 et CRC32 uniquement)
+    pub intra_file_parallel: bool,
+    /// Taille du tampon de lecture en mode streaming (non nulle)
+    pub buffer_size: usize,
+    /// Limite partagée du nombre de fichiers lus en même temps (None : pas de limite). Exclut
+    /// le parallélisme interne à un fichier, `intra_file_parallel` compris (voir [`ReadGate`]).
+    pub read_gate: Option<&'static ReadGate>,
+    /// Fichiers .gz, .zst et .bz2 hachés après décompression (voir [`Compression`])
+    pub decompress: bool,
+    /// Table d'un CRC32 non standard, --crc-* (None : CRC-32 IEEE via crc32fast)
+    pub crc32_table: Option<&'static Crc32Table>,
+    /// Variante de CRC-16 calculée pour [`HashAlgo::Crc16`]
+    pub crc16: Crc16Variant,
+    /// Longueur du digest BLAKE2b en octets, de 1 à [`BLAKE2B_MAX_LEN`]. Paramètre de
+    /// l'algorithme : un digest de 32 octets n'est pas le début de celui de 64.
+    pub blake2b_len: usize,
+    /// Clé HMAC : digest authentifié (HMAC-SHA256...) à la place du digest nu. Ignorée pour
+    /// les algorithmes non cryptographiques (voir [`HashAlgo::supports_hmac`]).
+    pub hmac_key: Option<&'static [u8]>,
+    /// Seuls les premiers octets de chaque fichier sont hachés (digest partiel, pré-filtre
+    /// de doublons) ; la taille rendue reste celle du fichier entier
+    pub head: Option<u64>,
+    /// Mode, propriétaire et taille du fichier hachés avant son contenu : le digest change
+    /// aussi avec les attributs, pas seulement avec le contenu. Sans effet sur [`hash_reader`].
+    pub metadata: bool,
+}
+
+impl Default for HashConfig {
+    fn default() -> Self {
+        HashConfig {
+            algo: HashAlgo::Xxh3,
+            full_load_limit: DEFAULT_FULL_LOAD_LIMIT,
+            mmap: false,
+            intra_file_parallel: false,
+            buffer_size: DEFAULT_BUFFER_SIZE,
+            read_gate: None,
+            decompress: false,
+            crc32_table: None,
+            crc16: Crc16Variant::Ccitt,
+            blake2b_len: BLAKE2B_MAX_LEN,
+            hmac_key: None,
+            head: None,
+            metadata: false,
+        }
+    }
+}
+
+/// Paramètres d'un CRC32 table-driven, au sens du catalogue de Rocksoft : polynôme en
+/// notation normale (bit de poids fort en tête, x^32 implicite), valeur initiale du
+/// registre, XOR final et sens de traitement des bits. `reflected` vaut pour l'entrée
+/// comme pour la sortie (refin = refout), ce que partagent les CRC-32 usuels.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Crc32Spec {
+    pub poly: u32,
+    pub init: u32,
+    pub xorout: u32,
+    /// true : octets traités bit de poids faible en tête (IEEE, Castagnoli) ;
+    /// false : bit de poids fort en tête (BZIP2, MPEG-2, POSIX)
+    pub reflected: bool,
+}
+
+impl Crc32Spec {
+    /// CRC-32 standard (IEEE 802.3, zlib), calculé par crc32fast
+    pub const IEEE: Crc32Spec = Crc32Spec {
+        poly: 0x04C1_1DB7,
+        init: 0xFFFF_FFFF,
+        xorout: 0xFFFF_FFFF,
+        reflected: true,
+    };
+}
+
+/// Table de recherche d'un CRC32 quelconque (voir [`Crc32Spec`])
+pub struct Crc32Table {
+    table: [u32; 256],
+    spec: Crc32Spec,
+}
+
+impl Crc32Table {
+    pub fn new(spec: Crc32Spec) -> Self {
+        let mut table = [0u32; 256];
+        for (i, entry) in table.iter_mut().enumerate() {
+            *entry = if spec.reflected {
+                // Polynôme réfléchi, registre décalé vers la droite
+                let poly = spec.poly.reverse_bits();
+                let mut c = i as u32;
+                for _ in 0..8 {
+                    c = if c & 1 != 0 { poly ^ (c >> 1) } else { c >> 1 };
+                }
+                c
+            } else {
+                let mut c = (i as u32) << 24;
+                for _ in 0..8 {
+                    c = if c & 0x8000_0000 != 0 {
+                        spec.poly ^ (c << 1)
+                    } else {
+                        c << 1
+                    };
+                }
+                c
+            };
+        }
+        Crc32Table { table, spec }
+    }
+
+    pub fn spec(&self) -> Crc32Spec {
+        self.spec
+    }
+}
+
+/// Variante de CRC-16 (noms usuels ; entre parenthèses, celui du catalogue de Rocksoft).
+/// Aucune n'applique de XOR final.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, ValueEnum)]
+pub enum Crc16Variant {
+    /// Polynôme 0x1021, init 0xFFFF, non réfléchi (CRC-16/IBM-3740, ex-CCITT-FALSE)
+    Ccitt,
+    /// Polynôme 0x8005, init 0xFFFF, réfléchi (CRC-16/MODBUS)
+    Modbus,
+    /// Polynôme 0x1021, init 0, non réfléchi (CRC-16/XMODEM)
+    Xmodem,
+}
+
+// Tables calculées à la compilation : CCITT et XMODEM partagent le même polynôme
+static CRC16_1021: [u16; 256] = crc16_table(0x1021, false);
+static CRC16_8005_REFLECTED: [u16; 256] = crc16_table(0x8005, true);
+
+impl Crc16Variant {
+    // (table, valeur initiale du registre, réfléchi)
+    fn params(self) -> (&'static [u16; 256], u16, bool) {
+        match self {
+            Crc16Variant::Ccitt => (&CRC16_1021, 0xFFFF, false),
+            Crc16Variant::Modbus => (&CRC16_8005_REFLECTED, 0xFFFF, true),
+            Crc16Variant::Xmodem => (&CRC16_1021, 0, false),
+        }
+    }
+}
+
+// Même construction que Crc32Table::new, sur 16 bits
+const fn crc16_table(poly: u16, reflected: bool) -> [u16; 256] {
+    let mut table = [0u16; 256];
+    let mut i = 0;
+    while i < 256 {
+        let mut c;
+        let mut bit = 0;
+        if reflected {
+            let poly = poly.reverse_bits();
+            c = i as u16;
+            while bit < 8 {
+                c = if c & 1 != 0 { poly ^ (c >> 1) } else { c >> 1 };
+                bit += 1;
+            }
+        } else {
+            c = (i as u16) << 8;
+            while bit < 8 {
+                c = if c & 0x8000 != 0 {
+                    poly ^ (c << 1)
+                } else {
+                    c << 1
+                };
+                bit += 1;
+            }
+        }
+        table[i] = c;
+        i += 1;
+    }
+    table
+}
+
+/// Format de compression reconnu à l'extension, pour hacher le contenu décompressé
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Compression {
+    Gzip,
+    Zstd,
+    Bzip2,
+}
+
+impl Compression {
+    /// None pour une extension inconnue : le fichier est alors haché tel quel
+    pub fn from_path(path: &Path) -> Option<Self> {
+        let ext = path.extension()?.to_str()?.to_ascii_lowercase();
+        match ext.as_str() {
+            "gz" => Some(Compression::Gzip),
+            "zst" => Some(Compression::Zstd),
+            "bz2" => Some(Compression::Bzip2),
+            _ => None,
+        }
+    }
+
+    // Les membres concaténés (gzip -c a b, pbzip2) sont décompressés à la suite
+    fn decoder(self, file: File) -> io::Result<Box<dyn Read>> {
+        Ok(match self {
+            Compression::Gzip => Box::new(flate2::read::MultiGzDecoder::new(file)),
+            Compression::Zstd => Box::new(zstd::stream::read::Decoder::new(file)?),
+            Compression::Bzip2 => Box::new(bzip2::read::MultiBzDecoder::new(file)),
+        })
+    }
+}
+
+// Compression à appliquer à `path` selon `config` (None : contenu brut)
+fn compression_of(path: &Path, config: &HashConfig) -> Option<Compression> {
+    config
+        .decompress
+        .then(|| Compression::from_path(path))
+        .flatten()
+}
+
+/// Nombre maximal de lectures de fichiers simultanées, indépendant du nombre de threads.
+/// Sur disque rotatif, les têtes ne font plus d'allers-retours entre des dizaines de
+/// fichiers ; le hachage des données déjà lues continue, lui, en parallèle.
+///
+/// Un thread qui attend une place bloque sans rendre la main à rayon. Le hachage fait
+/// place tenue ne lance donc aucun travail rayon imbriqué (BLAKE3 `update_rayon`, blocs
+/// de `intra_file_parallel`) : en attendant la fin d'un `join`, le détenteur pourrait
+/// voler la tâche d'un autre fichier et bloquer à son tour dans `acquire`.
+pub struct ReadGate {
+    available: Mutex<usize>,
+    released: Condvar,
+}
+
+impl ReadGate {
+    pub fn new(permits: usize) -> Self {
+        ReadGate {
+            available: Mutex::new(permits.max(1)),
+            released: Condvar::new(),
+        }
+    }
+
+    // Bloque jusqu'à ce qu'une lecture se termine si toutes les places sont prises
+    fn acquire(&self) -> ReadPermit<'_> {
+        let mut available = self.available.lock().unwrap_or_else(|e| e.into_inner());
+        while *available == 0 {
+            available = self
+                .released
+                .wait(available)
+                .unwrap_or_else(|e| e.into_inner());
+        }
+        *available -= 1;
+        ReadPermit(self)
+    }
+}
+
+// Place rendue à la destruction
+struct ReadPermit<'a>(&'a ReadGate);
+
+impl Drop for ReadPermit<'_> {
+    fn drop(&mut self) {
+        *self.0.available.lock().unwrap_or_else(|e| e.into_inner()) += 1;
+        self.0.released.notify_one();
+    }
+}
+
+/// Digest brut d'un fichier, nombre d'octets hachés et date de modification
+/// (None si la plateforme ne la fournit pas)
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FileDigest {
+    pub bytes: Vec<u8>,
+    pub size: u64,
+    pub modified: Option<SystemTime>,
+    /// Le nombre d'octets hachés diffère de la taille lue avant la lecture : le fichier a
+    /// grossi ou rétréci pendant le hachage (`size` est alors le nombre réellement haché)
+    pub changed: bool,
+}
+
+impl FileDigest {
+    /// Représentation hexadécimale minuscule, sans séparateur
+    pub fn to_hex(&self) -> String {
+        self.bytes.iter().map(|b| format!("{:02x}", b)).collect()
+    }
+}
+
+/// Catégorie d'une erreur de hachage : distingue un fichier disparu d'un accès refusé
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum ErrorClass {
+    NotFound,
+    PermissionDenied,
+    Other,
+}
+
+impl ErrorClass {
+    pub fn of(err: &io::Error) -> Self {
+        match err.kind() {
+            io::ErrorKind::NotFound => ErrorClass::NotFound,
+            io::ErrorKind::PermissionDenied => ErrorClass::PermissionDenied,
+            _ => ErrorClass::Other,
+        }
+    }
+
+    /// Libellé stable, utilisé dans les manifestes
+    pub fn label(self) -> &'static str {
+        match self {
+            ErrorClass::NotFound => "not-found",
+            ErrorClass::PermissionDenied => "permission-denied",
+            ErrorClass::Other => "io",
+        }
+    }
+}
+
+// Erreurs pour lesquelles une nouvelle lecture a des chances d'aboutir
+fn is_transient(err: &io::Error) -> bool {
+    matches!(
+        err.kind(),
+        io::ErrorKind::Interrupted | io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut
+    )
+}
+
+/// Hache un fichier selon `config`. Les sommes entières (CRC, XXH3, City) sont
+/// rendues en big-endian, de sorte que leur hexadécimal corresponde à l'entier.
+/// Les erreurs transitoires sont retentées quelques fois avec un délai croissant.
+pub fn hash_file(path: &Path, config: &HashConfig) -> io::Result<FileDigest> {
+    let mut digests = hash_file_multi(path, &[config.algo], config)?;
+    Ok(digests.remove(0))
+}
+
+/// Comme [`hash_file`], pour plusieurs algorithmes en une seule lecture du fichier.
+/// `config.algo` est ignoré ; les digests suivent l'ordre de `algos`.
+pub fn hash_file_multi(
+    path: &Path,
+    algos: &[HashAlgo],
+    config: &HashConfig,
+) -> io::Result<Vec<FileDigest>> {
+    let path = long_path(path);
+    with_retries(|| hash_file_once(&path, algos, config))
+}
+
+/// Comme [`hash_file_multi`], en deux étages : la lecture reste sur le thread appelant
+/// (pool de lecture) et les blocs lus sont hachés sur `hash_pool`, via un canal borné.
+/// City128, le mmap, le hachage parallèle intra-fichier, les fichiers compressés et
+/// [`HashConfig::head`] sont traités comme dans [`hash_file_multi`].
+pub fn hash_file_pipelined(
+    path: &Path,
+    algos: &[HashAlgo],
+    config: &HashConfig,
+    hash_pool: &rayon::ThreadPool,
+) -> io::Result<Vec<FileDigest>> {
+    if algos.contains(&HashAlgo::City128)
+        || config.mmap
+        || config.intra_file_parallel
+        || config.head.is_some()
+        || config.metadata
+        || compression_of(path, config).is_some()
+    {
+        return hash_file_multi(path, algos, config);
+    }
+    let path = long_path(path);
+    with_retries(|| hash_pipelined_once(&path, algos, config, hash_pool))
+}
+
+/// Chemin ouvrable au-delà de MAX_PATH (260 caractères) sous Windows : rendu absolu puis
+/// préfixé par `\\?\` (`\\?\UNC\` pour un partage réseau). Ce préfixe désactive la
+/// normalisation de Windows, d'où le passage par `path::absolute` (résout `..` et `/`).
+/// Inchangé sur les autres plateformes ou s'il est déjà préfixé.
+#[cfg(windows)]
+pub fn long_path(path: &Path) -> std::borrow::Cow<'_, Path> {
+    use std::path::{Component, Prefix};
+    let Ok(absolute) = std::path::absolute(path) else {
+        return path.into();
+    };
+    let mut components = absolute.components();
+    let prefixed = match components.next() {
+        Some(Component::Prefix(prefix)) => match prefix.kind() {
+            Prefix::Disk(_) => {
+                let mut long = std::ffi::OsString::from(r"\\?\");
+                long.push(absolute.as_os_str());
+                long
+            }
+            Prefix::UNC(server, share) => {
+                let mut long = std::ffi::OsString::from(r"\\?\UNC\");
+                long.push(server);
+                long.push(r"\");
+                long.push(share);
+                long.push(components.as_path().as_os_str());
+                long
+            }
+            // Déjà verbatim (\\?\, \\.\) : tel quel
+            _ => return path.into(),
+        },
+        _ => return path.into(),
+    };
+    std::path::PathBuf::from(prefixed).into()
+}
+
+#[cfg(not(windows))]
+pub fn long_path(path: &Path) -> std::borrow::Cow<'_, Path> {
+    path.into()
+}
+
+/// Inverse de [`long_path`] pour l'affichage et les manifestes : `\\?\C:\x` redevient
+/// `C:\x` et `\\?\UNC\serveur\partage` redevient `\\serveur\partage`
+#[cfg(windows)]
+pub fn strip_long_path(path: &Path) -> std::borrow::Cow<'_, Path> {
+    use std::path::{Component, Prefix};
+    let mut components = path.components();
+    let Some(Component::Prefix(prefix)) = components.next() else {
+        return path.into();
+    };
+    let mut plain = match prefix.kind() {
+        Prefix::VerbatimDisk(letter) => std::ffi::OsString::from(format!("{}:", letter as char)),
+        Prefix::VerbatimUNC(server, share) => {
+            let mut plain = std::ffi::OsString::from(r"\\");
+            plain.push(server);
+            plain.push(r"\");
+            plain.push(share);
+            plain
+        }
+        _ => return path.into(),
+    };
+    plain.push(components.as_path().as_os_str());
+    std::path::PathBuf::from(plain).into()
+}
+
+#[cfg(not(windows))]
+pub fn strip_long_path(path: &Path) -> std::borrow::Cow<'_, Path> {
+    path.into()
+}
+
+// Nouvelles tentatives sur erreur transitoire, avec un délai croissant
+fn with_retries<T>(mut attempt: impl FnMut() -> io::Result<T>) -> io::Result<T> {
+    let mut delay = RETRY_BASE_DELAY;
+    for _ in 0..TRANSIENT_RETRIES {
+        match attempt() {
+            Err(e) if is_transient(&e) => {
+                thread::sleep(delay);
+                delay *= 2;
+            }
+            result => return result,
+        }
+    }
+    attempt()
+}
+
+fn hash_pipelined_once(
+    path: &Path,
+    algos: &[HashAlgo],
+    config: &HashConfig,
+    hash_pool: &rayon::ThreadPool,
+) -> io::Result<Vec<FileDigest>> {
+    let permit = config.read_gate.map(ReadGate::acquire);
+    let meta = fs::metadata(path)?;
+    let size = meta.len();
+    let modified = meta.modified().ok();
+    let mut file = File::open(path)?;
+
+    // Étage de hachage : consomme les blocs dans l'ordre jusqu'à la fermeture du canal.
+    // Le canal borné limite la mémoire quand la lecture va plus vite que le hachage.
+    let (chunk_tx, chunk_rx) = mpsc::sync_channel::<Vec<u8>>(PIPELINE_DEPTH);
+    let (digest_tx, digest_rx) = mpsc::sync_channel(1);
+    let mut hashers: Vec<_> = algos
+        .iter()
+        .map(|&algo| make_stream(algo, config))
+        .collect();
+    hash_pool.spawn(move || {
+        for chunk in chunk_rx {
+            for hasher in &mut hashers {
+                hasher.update(&chunk);
+            }
+        }
+        let digests: Vec<_> = hashers.iter_mut().map(|hasher| hasher.finalize()).collect();
+        // Le lecteur a pu abandonner sur erreur : le résultat est alors sans destinataire
+        let _ = digest_tx.send(digests);
+    });
+
+    // Étage de lecture ; un petit fichier tient dans un seul bloc à sa taille
+    let chunk_size = size.clamp(1, config.buffer_size.max(1) as u64) as usize;
+    let mut hashed = 0;
+    loop {
+        let mut chunk = vec![0; chunk_size];
+        let n = file.read(&mut chunk)?;
+        if n == 0 {
+            break;
+        }
+        hashed += n as u64;
+        chunk.truncate(n);
+        if chunk_tx.send(chunk).is_err() {
+            break;
+        }
+    }
+    drop(chunk_tx);
+    drop(permit);
+
+    let digests = digest_rx
+        .recv()
+        .map_err(|_| io::Error::other("Étage de hachage interrompu"))?;
+    Ok(digests
+        .into_iter()
+        .map(|bytes| FileDigest {
+            bytes,
+            size: hashed,
+            modified,
+            changed: hashed != size,
+        })
+        .collect())
+}
+
+fn hash_file_once(
+    path: &Path,
+    algos: &[HashAlgo],
+    config: &HashConfig,
+) -> io::Result<Vec<FileDigest>> {
+    // La place est gardée pendant toute la lecture : jusqu'au hachage pour un petit fichier
+    // lu d'un bloc, jusqu'à la fin pour le streaming et le mmap (lecture au fil du calcul)
+    let permit = config.read_gate.map(ReadGate::acquire);
+    let meta = fs::metadata(path)?;
+    let size = meta.len();
+    let modified = meta.modified().ok();
+    let mut file = File::open(path)?;
+
+    // Octets réellement hachés, comparés à `size` : un fichier en cours d'écriture peut
+    // changer de taille entre le stat et la lecture
+    let (digests, hashed) = if config.head.is_some() || config.metadata {
+        // --head : lecture limitée aux premiers octets, en streaming ; la taille enregistrée
+        // reste celle du fichier, un fichier plus court que `head` est haché en entier.
+        // Les attributs (HashConfig::metadata) précèdent le contenu dans le même flux.
+        let head = config.head.unwrap_or(u64::MAX);
+        let prefix = if config.metadata {
+            metadata_prefix(&meta)
+        } else {
+            Vec::new()
+        };
+        let mut counter = CountingReader {
+            inner: file.take(head),
+            count: 0,
+        };
+        let digests = hash_decoded(prefix.as_slice().chain(&mut counter), algos, config)?;
+        let hashed = if counter.count == size.min(head) {
+            size
+        } else {
+            counter.count
+        };
+        (digests, hashed)
+    } else if let Some(compression) = compression_of(path, config) {
+        // --decompress : le contenu décompressé est toujours lu en streaming ; la taille
+        // enregistrée reste celle du fichier compressé
+        (
+            hash_decoded(compression.decoder(file)?, algos, config)?,
+            size,
+        )
+    } else if size <= config.full_load_limit {
+        // --------------------------------------------------------------------------------
+        // CAS 1: PETIT FICHIER (Charge complète en mémoire pour une performance maximale)
+        // --------------------------------------------------------------------------------
+        // En mode mmap, on hache directement la projection mémoire (pas d'allocation
+        // par fichier). Repli sur une lecture classique si le mmap échoue (fichiers spéciaux...).
+        // SAFETY: projection en lecture seule, limitée à la durée du calcul.
+        let map = if config.mmap {
+            unsafe { Mmap::map(&file) }.ok()
+        } else {
+            None
+        };
+        let mut buf = Vec::new();
+        let data: &[u8] = match &map {
+            Some(map) => map,
+            None => {
+                buf.reserve_exact(size as usize);
+                file.read_to_end(&mut buf)?;
+                drop(permit);
+                &buf
+            }
+        };
+        let digests = algos
+            .iter()
+            .map(|&algo| digest_slice(data, algo, config))
+            .collect();
+        (digests, data.len() as u64)
+    } else {
+        // --------------------------------------------------------------------------------
+        // CAS 2: GRAND FICHIER (Mode streaming pour économiser la RAM)
+        // --------------------------------------------------------------------------------
+        hash_large_file(&mut file, size, algos, config)?
+    };
+
+    Ok(digests
+        .into_iter()
+        .map(|bytes| FileDigest {
+            bytes,
+            size: hashed,
+            modified,
+            changed: hashed != size,
+        })
+        .collect())
+}
+
+// Contenu décompressé : sa taille n'est pas connue d'avance, il est donc toujours lu en
+// streaming (en entier pour City128, qui n'est pas incrémental)
+fn hash_decoded(
+    mut reader: impl Read,
+    algos: &[HashAlgo],
+    config: &HashConfig,
+) -> io::Result<Vec<Vec<u8>>> {
+    if algos.contains(&HashAlgo::City128) {
+        let mut data = Vec::new();
+        reader.read_to_end(&mut data)?;
+        return Ok(algos
+            .iter()
+            .map(|&algo| digest_slice(&data, algo, config))
+            .collect());
+    }
+    hash_stream(&mut reader, algos, config)
+}
+
+// Octets hachés avant le contenu avec HashConfig::metadata : une étiquette de version puis,
+// en petit-boutiste, les bits de permission (u32), l'uid et le gid (u32) et la taille (u64).
+// Hors Unix, seul l'attribut lecture seule est repris (0o444 ou 0o644, uid et gid à 0).
+fn metadata_prefix(meta: &fs::Metadata) -> Vec<u8> {
+    #[cfg(unix)]
+    let (mode, uid, gid) = {
+        use std::os::unix::fs::MetadataExt;
+        (meta.mode() & 0o7777, meta.uid(), meta.gid())
+    };
+    #[cfg(not(unix))]
+    let (mode, uid, gid): (u32, u32, u32) = (
+        if meta.permissions().readonly() {
+            0o444
+        } else {
+            0o644
+        },
+        0,
+        0,
+    );
+    let mut prefix = b"zhashgen-metadata-v1\0".to_vec();
+    prefix.extend(mode.to_le_bytes());
+    prefix.extend(uid.to_le_bytes());
+    prefix.extend(gid.to_le_bytes());
+    prefix.extend(meta.len().to_le_bytes());
+    prefix
+}
+
+// Digest d'un contenu entièrement en mémoire : même implémentation que le streaming,
+// en un seul bloc, pour qu'un fichier donne le même digest de part et d'autre de
+// full_load_limit
+fn digest_slice(data: &[u8], algo: HashAlgo, config: &HashConfig) -> Vec<u8> {
+    if let HashAlgo::City128 = algo {
+        return city_hash_128(data).to_be_bytes().to_vec();
+    }
+    let mut hasher = make_stream(algo, config);
+    hasher.update(data);
+    hasher.finalize()
+}
+
+// Gros fichier : City128 et le hachage parallèle travaillent sur une projection mémoire,
+// les autres algorithmes se partagent une seule lecture en streaming (trous des fichiers
+// creux sautés sous Linux). Retourne aussi le nombre d'octets hachés : celui de la
+// lecture en streaming, à défaut la taille de la dernière projection.
+fn hash_large_file(
+    file: &mut File,
+    size: u64,
+    algos: &[HashAlgo],
+    config: &HashConfig,
+) -> io::Result<(Vec<Vec<u8>>, u64)> {
+    let mut hashed = size;
+    let mut digests = Vec::with_capacity(algos.len());
+    for &algo in algos {
+        // CityHash n'est pas incrémental : il lui faut tout le contenu d'un coup.
+        // On projette le fichier en mémoire (mmap) et on passe la tranche complète,
+        // ce qui évite d'allouer un tampon de la taille du fichier sur le tas :
+        // les pages sont chargées puis libérées par le noyau au fil de la lecture.
+        let digest = if let HashAlgo::City128 = algo {
+            // SAFETY: le fichier est ouvert en lecture seule et la projection ne vit
+            // que le temps du calcul. Une troncature concurrente par un autre
+            // processus reste un risque inhérent au mmap (SIGBUS).
+            let map = unsafe { Mmap::map(&*file)? };
+            hashed = map.len() as u64;
+            Some(city_hash_128(&map).to_be_bytes().to_vec())
+        } else if config.intra_file_parallel {
+            let digest = hash_parallel(file, algo, config)?;
+            if digest.is_some() {
+                // Taille lue par Mmap::map au moment de la projection
+                hashed = file.metadata()?.len();
+            }
+            digest
+        } else {
+            None
+        };
+        digests.push(digest);
+    }
+
+    let remaining: Vec<HashAlgo> = algos
+        .iter()
+        .zip(&digests)
+        .filter(|(_, digest)| digest.is_none())
+        .map(|(&algo, _)| algo)
+        .collect();
+    if remaining.is_empty() {
+        return Ok((digests.into_iter().flatten().collect(), hashed));
+    }
+    // Un fichier creux est haché sur exactement `size` octets (troncature : erreur)
+    let streamed = match hash_sparse(file, size, &remaining, config)? {
+        Some(streamed) => {
+            hashed = size;
+            streamed
+        }
+        None => {
+            let mut counter = CountingReader {
+                inner: &mut *file,
+                count: 0,
+            };
+            let streamed = hash_stream(&mut counter, &remaining, config)?;
+            hashed = counter.count;
+            streamed
+        }
+    };
+    let mut streamed = streamed.into_iter();
+    let digests = digests
+        .into_iter()
+        .map(|digest| digest.or_else(|| streamed.next()).unwrap_or_default())
+        .collect();
+    Ok((digests, hashed))
+}
+
+// Fichier creux (images de VM...) : les zones de données, repérées par SEEK_DATA et
+// SEEK_HOLE, sont lues ; les trous sont hachés comme les zéros qu'ils représentent, sans
+// lecture disque. None (curseur remis au début) si le fichier n'a aucun trou ou si le
+// système de fichiers ne sait pas les signaler : l'appelant lit alors tout le fichier.
+#[cfg(target_os = "linux")]
+fn hash_sparse(
+    file: &mut File,
+    size: u64,
+    algos: &[HashAlgo],
+    config: &HashConfig,
+) -> io::Result<Option<Vec<Vec<u8>>>> {
+    use std::io::{Seek, SeekFrom};
+    use std::os::fd::AsRawFd;
+
+    let fd = file.as_raw_fd();
+    // Some(position) ; None pour ENXIO (plus aucune donnée après `offset`)
+    let seek = |offset: u64, whence| -> io::Result<Option<u64>> {
+        // SAFETY: lseek sur un descripteur ouvert, sans accès mémoire
+        let position = unsafe { libc::lseek(fd, offset as libc::off_t, whence) };
+        if position >= 0 {
+            return Ok(Some(position as u64));
+        }
+        let err = io::Error::last_os_error();
+        match err.raw_os_error() {
+            Some(libc::ENXIO) => Ok(None),
+            _ => Err(err),
+        }
+    };
+    // Sans trou avant la fin (ou sans support : EINVAL...), rien à gagner
+    let first_hole = seek(0, libc::SEEK_HOLE).ok().flatten();
+    file.seek(SeekFrom::Start(0))?;
+    if first_hole.is_none_or(|hole| hole >= size) {
+        return Ok(None);
+    }
+
+    let mut hashers: Vec<_> = algos
+        .iter()
+        .map(|&algo| make_stream(algo, config))
+        .collect();
+    STREAM_BUFFER.with_borrow_mut(|buffer| -> io::Result<()> {
+        buffer.resize(config.buffer_size.max(1), 0);
+        let mut position = 0;
+        while position < size {
+            let data = seek(position, libc::SEEK_DATA)?.unwrap_or(size).min(size);
+            // Trou [position, data) : des zéros, par blocs du tampon
+            let mut zeros = data - position;
+            if zeros > 0 {
+                buffer.fill(0);
+            }
+            while zeros > 0 {
+                let n = zeros.min(buffer.len() as u64) as usize;
+                for hasher in &mut hashers {
+                    hasher.update(&buffer[..n]);
+                }
+                zeros -= n as u64;
+            }
+            if data == size {
+                break;
+            }
+            let hole = seek(data, libc::SEEK_HOLE)?.unwrap_or(size).min(size);
+            file.seek(SeekFrom::Start(data))?;
+            let mut remaining = hole - data;
+            while remaining > 0 {
+                let want = remaining.min(buffer.len() as u64) as usize;
+                let n = file.read(&mut buffer[..want])?;
+                if n == 0 {
+                    return Err(io::Error::new(
+                        io::ErrorKind::UnexpectedEof,
+                        "Fichier tronqué pendant la lecture",
+                    ));
+                }
+                for hasher in &mut hashers {
+                    hasher.update(&buffer[..n]);
+                }
+                remaining -= n as u64;
+            }
+            position = hole;
+        }
+        Ok(())
+    })?;
+    Ok(Some(
+        hashers.iter_mut().map(|hasher| hasher.finalize()).collect(),
+    ))
+}
+
+#[cfg(not(target_os = "linux"))]
+fn hash_sparse(
+    _file: &mut File,
+    _size: u64,
+    _algos: &[HashAlgo],
+    _config: &HashConfig,
+) -> io::Result<Option<Vec<Vec<u8>>>> {
+    Ok(None)
+}
+
+/// Hache un flux (ex. stdin) par blocs, sans connaître sa taille à l'avance.
+/// CityHash n'étant pas incrémental, son entrée est lue entièrement en mémoire.
+pub fn hash_reader(reader: impl Read, config: &HashConfig) -> io::Result<FileDigest> {
+    let mut counter = CountingReader {
+        inner: reader.take(config.head.unwrap_or(u64::MAX)),
+        count: 0,
+    };
+    let bytes = if let HashAlgo::City128 = config.algo {
+        let mut data = Vec::new();
+        counter.read_to_end(&mut data)?;
+        city_hash_128(&data).to_be_bytes().to_vec()
+    } else {
+        hash_stream(&mut counter, &[config.algo], config)?.remove(0)
+    };
+    Ok(FileDigest {
+        bytes,
+        size: counter.count,
+        modified: None,
+        changed: false,
+    })
+}
+
+// Compte les octets lus pour renseigner la taille d'un flux
+struct CountingReader<R> {
+    inner: R,
+    count: u64,
+}
+
+impl<R: Read> Read for CountingReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = self.inner.read(buf)?;
+        self.count += n as u64;
+        Ok(n)
+    }
+}
+
+// Boucle de lecture du mode streaming, avec le tampon réutilisé du thread courant :
+// chaque bloc lu alimente tous les algorithmes demandés
+fn hash_stream(
+    reader: &mut impl Read,
+    algos: &[HashAlgo],
+    config: &HashConfig,
+) -> io::Result<Vec<Vec<u8>>> {
+    let mut hashers: Vec<_> = algos
+        .iter()
+        .map(|&algo| make_stream(algo, config))
+        .collect();
+
+    STREAM_BUFFER.with_borrow_mut(|buffer| -> io::Result<()> {
+        buffer.resize(config.buffer_size.max(1), 0);
+        loop {
+            let n = reader.read(buffer)?;
+            if n == 0 {
+                break;
+            }
+            for hasher in &mut hashers {
+                hasher.update(&buffer[..n]);
+            }
+        }
+        Ok(())
+    })?;
+
+    Ok(hashers.iter_mut().map(|hasher| hasher.finalize()).collect())
+}
+
+// Implémentation incrémentale d'un algorithme
+fn make_stream(algo: HashAlgo, config: &HashConfig) -> Box<dyn HashingStream> {
+    if let Some(key) = config.hmac_key.filter(|_| algo.supports_hmac()) {
+        return make_hmac_stream(algo, key);
+    }
+    match algo {
+        HashAlgo::Crc16 => Box::new(Crc16Stream::new(config.crc16)),
+        HashAlgo::Crc32 => match config.crc32_table {
+            Some(table) => Box::new(TableCrc32Stream::new(table)),
+            None => Box::new(Crc32Stream::new()),
+        },
+        HashAlgo::Crc32c => Box::new(Crc32cStream::new()),
+        HashAlgo::Crc64 => Box::new(Crc64Stream::new()),
+        HashAlgo::Adler32 => Box::new(Adler32Stream::new()),
+        HashAlgo::Xxh3 => Box::new(Xxh3Stream::new()),
+        HashAlgo::Xxh3_128 => Box::new(Xxh3_128Stream::new()),
+        HashAlgo::Fnv1a => Box::new(Fnv1aStream::new()),
+        HashAlgo::Murmur3 => Box::new(Murmur3Stream::new()),
+        HashAlgo::Md5 => Box::new(CryptoStream::<Md5>::new()),
+        HashAlgo::Sha1 => Box::new(CryptoStream::<Sha1>::new()),
+        HashAlgo::Sha256 => Box::new(CryptoStream::<Sha256>::new()),
+        HashAlgo::Sha512 => Box::new(CryptoStream::<Sha512>::new()),
+        HashAlgo::Sha384 => Box::new(CryptoStream::<Sha384>::new()),
+        HashAlgo::Sha512_256 => Box::new(CryptoStream::<Sha512_256>::new()),
+        HashAlgo::Sha3_256 => Box::new(CryptoStream::<Sha3_256>::new()),
+        HashAlgo::Sha3_512 => Box::new(CryptoStream::<Sha3_512>::new()),
+        HashAlgo::Blake2b if config.blake2b_len != BLAKE2B_MAX_LEN => {
+            Box::new(Blake2bVarStream::new(config.blake2b_len))
+        }
+        HashAlgo::Blake2b => Box::new(CryptoStream::<Blake2b512>::new()),
+        HashAlgo::Blake2s => Box::new(CryptoStream::<Blake2s256>::new()),
+        HashAlgo::Blake3 => Box::new(Blake3Stream::new(config.read_gate.is_none())),
+        // City128 n'est pas incrémental : traité par les appelants
+        HashAlgo::City128 => unreachable!(),
+    }
+}
+
+// BLAKE2 n'a pas la structure interne exigée par Hmac : SimpleHmac, plus lent, convient
+fn make_hmac_stream(algo: HashAlgo, key: &'static [u8]) -> Box<dyn HashingStream> {
+    match algo {
+        HashAlgo::Md5 => Box::new(HmacStream::<Hmac<Md5>>::new(key)),
+        HashAlgo::Sha1 => Box::new(HmacStream::<Hmac<Sha1>>::new(key)),
+        HashAlgo::Sha256 => Box::new(HmacStream::<Hmac<Sha256>>::new(key)),
+        HashAlgo::Sha512 => Box::new(HmacStream::<Hmac<Sha512>>::new(key)),
+        HashAlgo::Sha384 => Box::new(HmacStream::<Hmac<Sha384>>::new(key)),
+        HashAlgo::Sha512_256 => Box::new(HmacStream::<Hmac<Sha512_256>>::new(key)),
+        HashAlgo::Sha3_256 => Box::new(HmacStream::<Hmac<Sha3_256>>::new(key)),
+        HashAlgo::Sha3_512 => Box::new(HmacStream::<Hmac<Sha3_512>>::new(key)),
+        HashAlgo::Blake2b => Box::new(HmacStream::<SimpleHmac<Blake2b512>>::new(key)),
+        HashAlgo::Blake2s => Box::new(HmacStream::<SimpleHmac<Blake2s256>>::new(key)),
+        // Filtré par make_stream (HashAlgo::supports_hmac)
+        _ => unreachable!(),
+    }
+}
+
+// Hachage parallèle d'un seul gros fichier projeté en mémoire :
+// - BLAKE3 : arbre de Merkle interne, parcouru par update_rayon
+// - CRC32 : blocs indépendants recombinés dans l'ordre (crc32fast::Hasher::combine)
+// Retourne None pour les autres algorithmes, qui ne se prêtent pas au découpage, et pour
+// un CRC32 à polynôme personnalisé (pas de recombinaison hors crc32fast).
+// Retourne aussi None sous ReadGate.
+fn hash_parallel(file: &File, algo: HashAlgo, config: &HashConfig) -> io::Result<Option<Vec<u8>>> {
+    let custom_crc = algo == HashAlgo::Crc32 && config.crc32_table.is_some();
+    let gated = config.read_gate.is_some();
+    if !matches!(algo, HashAlgo::Blake3 | HashAlgo::Crc32) || custom_crc || gated {
+        return Ok(None);
+    }
+    // SAFETY: projection en lecture seule, limitée à la durée du calcul.
+    let map = unsafe { Mmap::map(file)? };
+    let digest = match algo {
+        HashAlgo::Blake3 => Blake3Hasher::new()
+            .update_rayon(&map)
+            .finalize()
+            .as_bytes()
+            .to_vec(),
+        HashAlgo::Crc32 => {
+            let partials: Vec<Crc32Hasher> = map
+                .par_chunks(PARALLEL_CHUNK_SIZE)
+                .map(|chunk| {
+                    let mut hasher = Crc32Hasher::new();
+                    hasher.update(chunk);
+                    hasher
+                })
+                .collect();
+            let mut hasher = Crc32Hasher::new();
+            for partial in &partials {
+                hasher.combine(partial);
+            }
+            hasher.finalize().to_be_bytes().to_vec()
+        }
+        _ => unreachable!(),
+    };
+    Ok(Some(digest))
+}
+
+// --------------------------------------------------------------------------------
+// TRAITS ET STRUCTURES POUR LE STREAMING (lecture par blocs)
+// --------------------------------------------------------------------------------
+
+// Send : le hachage peut se faire sur un autre pool que la lecture (hash_file_pipelined)
+trait HashingStream: Send {
+    fn update(&mut self, data: &[u8]);
+    // Digest brut ; la mise en forme (hex...) relève de la couche de sortie
+    fn finalize(&mut self) -> Vec<u8>;
+}
+
+// Implémentation générique pour les hashes cryptographiques (SHA, Blake)
+struct CryptoStream<T: Digest + Send + 'static> {
+    hasher: T,
+}
+
+impl<T: Digest + Send + 'static> CryptoStream<T> {
+    fn new() -> Self {
+        CryptoStream { hasher: T::new() }
+    }
+}
+
+impl<T: Digest + Send + 'static> HashingStream for CryptoStream<T> {
+    fn update(&mut self, data: &[u8]) {
+        self.hasher.update(data);
+    }
+    fn finalize(&mut self) -> Vec<u8> {
+        // Swap out the current hasher with a fresh one and finalize the old
+        // instance. This avoids requiring the `FixedOutputReset` trait.
+        let hasher = std::mem::replace(&mut self.hasher, T::new());
+        hasher.finalize().to_vec()
+    }
+}
+
+// HMAC (RFC 2104) autour d'un hash cryptographique, recréé à partir de la clé après
+// chaque finalize comme CryptoStream
+struct HmacStream<M: Mac + KeyInit + Send + 'static> {
+    mac: M,
+    key: &'static [u8],
+}
+
+impl<M: Mac + KeyInit + Send + 'static> HmacStream<M> {
+    fn new(key: &'static [u8]) -> Self {
+        HmacStream {
+            mac: Self::keyed(key),
+            key,
+        }
+    }
+
+    fn keyed(key: &'static [u8]) -> M {
+        <M as KeyInit>::new_from_slice(key).expect("HMAC accepte une clé de toute longueur")
+    }
+}
+
+impl<M: Mac + KeyInit + Send + 'static> HashingStream for HmacStream<M> {
+    fn update(&mut self, data: &[u8]) {
+        self.mac.update(data);
+    }
+    fn finalize(&mut self) -> Vec<u8> {
+        let mac = std::mem::replace(&mut self.mac, Self::keyed(self.key));
+        mac.finalize().into_bytes().to_vec()
+    }
+}
+
+// Implémentation pour CRC32
+struct Crc32Stream {
+    hasher: Crc32Hasher,
+}
+
+impl Crc32Stream {
+    fn new() -> Self {
+        Crc32Stream {
+            hasher: Crc32Hasher::new(),
+        }
+    }
+}
+
+impl HashingStream for Crc32Stream {
+    fn update(&mut self, data: &[u8]) {
+        self.hasher.update(data);
+    }
+    fn finalize(&mut self) -> Vec<u8> {
+        self.hasher.clone().finalize().to_be_bytes().to_vec()
+    }
+}
+
+// Implémentation pour CRC32 paramétré (--crc-poly, --crc-init...), un octet à la fois
+struct TableCrc32Stream {
+    table: &'static Crc32Table,
+    crc: u32,
+}
+
+impl TableCrc32Stream {
+    fn new(table: &'static Crc32Table) -> Self {
+        let spec = table.spec;
+        // Le registre d'un CRC réfléchi est lui-même réfléchi, valeur initiale comprise
+        let crc = if spec.reflected {
+            spec.init.reverse_bits()
+        } else {
+            spec.init
+        };
+        TableCrc32Stream { table, crc }
+    }
+}
+
+impl HashingStream for TableCrc32Stream {
+    fn update(&mut self, data: &[u8]) {
+        let table = &self.table.table;
+        let mut crc = self.crc;
+        if self.table.spec.reflected {
+            for &b in data {
+                crc = (crc >> 8) ^ table[((crc as u8) ^ b) as usize];
+            }
+        } else {
+            for &b in data {
+                crc = (crc << 8) ^ table[(((crc >> 24) as u8) ^ b) as usize];
+            }
+        }
+        self.crc = crc;
+    }
+    fn finalize(&mut self) -> Vec<u8> {
+        (self.crc ^ self.table.spec.xorout).to_be_bytes().to_vec()
+    }
+}
+
+// Implémentation pour CRC-16 (table d'un octet, voir Crc16Variant)
+struct Crc16Stream {
+    table: &'static [u16; 256],
+    reflected: bool,
+    crc: u16,
+}
+
+impl Crc16Stream {
+    fn new(variant: Crc16Variant) -> Self {
+        let (table, init, reflected) = variant.params();
+        // Les valeurs initiales usuelles (0 et 0xFFFF) sont leur propre réflexion
+        Crc16Stream {
+            table,
+            reflected,
+            crc: init,
+        }
+    }
+}
+
+impl HashingStream for Crc16Stream {
+    fn update(&mut self, data: &[u8]) {
+        let table = self.table;
+        let mut crc = self.crc;
+        if self.reflected {
+            for &b in data {
+                crc = (crc >> 8) ^ table[((crc as u8) ^ b) as usize];
+            }
+        } else {
+            for &b in data {
+                crc = (crc << 8) ^ table[(((crc >> 8) as u8) ^ b) as usize];
+            }
+        }
+        self.crc = crc;
+    }
+    fn finalize(&mut self) -> Vec<u8> {
+        self.crc.to_be_bytes().to_vec()
+    }
+}
+
+// Implémentation pour CRC32C (crc32c_append : accélération matérielle SSE4.2/ARMv8)
+struct Crc32cStream {
+    digest: u32,
+}
+
+impl Crc32cStream {
+    fn new() -> Self {
+        Crc32cStream { digest: 0 }
+    }
+}
+
+impl HashingStream for Crc32cStream {
+    fn update(&mut self, data: &[u8]) {
+        self.digest = crc32c_append(self.digest, data);
+    }
+    fn finalize(&mut self) -> Vec<u8> {
+        self.digest.to_be_bytes().to_vec()
+    }
+}
+
+// Implémentation pour CRC64 (xz)
+struct Crc64Stream {
+    digest: crc::Digest<'static, u64>,
+}
+
+impl Crc64Stream {
+    fn new() -> Self {
+        Crc64Stream {
+            digest: CRC64_XZ.digest(),
+        }
+    }
+}
+
+impl HashingStream for Crc64Stream {
+    fn update(&mut self, data: &[u8]) {
+        self.digest.update(data);
+    }
+    fn finalize(&mut self) -> Vec<u8> {
+        self.digest.clone().finalize().to_be_bytes().to_vec()
+    }
+}
+
+// Implémentation pour Adler-32 (somme de contrôle des flux zlib)
+struct Adler32Stream {
+    hasher: adler2::Adler32,
+}
+
+impl Adler32Stream {
+    fn new() -> Self {
+        Adler32Stream {
+            hasher: adler2::Adler32::new(),
+        }
+    }
+}
+
+impl HashingStream for Adler32Stream {
+    fn update(&mut self, data: &[u8]) {
+        self.hasher.write_slice(data);
+    }
+    fn finalize(&mut self) -> Vec<u8> {
+        self.hasher.checksum().to_be_bytes().to_vec()
+    }
+}
+
+// Implémentation pour XXH3
+struct Xxh3Stream {
+    hasher: Xxh3,
+}
+
+impl Xxh3Stream {
+    fn new() -> Self {
+        Xxh3Stream {
+            hasher: Xxh3::new(),
+        }
+    }
+}
+
+impl HashingStream for Xxh3Stream {
+    fn update(&mut self, data: &[u8]) {
+        self.hasher.update(data);
+    }
+    fn finalize(&mut self) -> Vec<u8> {
+        self.hasher.digest().to_be_bytes().to_vec()
+    }
+}
+
+// Implémentation pour XXH3 128-bit (même état interne, finalisation différente)
+struct Xxh3_128Stream {
+    hasher: Xxh3,
+}
+
+impl Xxh3_128Stream {
+    fn new() -> Self {
+        Xxh3_128Stream {
+            hasher: Xxh3::new(),
+        }
+    }
+}
+
+impl HashingStream for Xxh3_128Stream {
+    fn update(&mut self, data: &[u8]) {
+        self.hasher.update(data);
+    }
+    fn finalize(&mut self) -> Vec<u8> {
+        self.hasher.digest128().to_be_bytes().to_vec()
+    }
+}
+
+// Implémentation pour FNV-1a 64 bits (octet par octet : plus lent que XXH3 sur les gros
+// fichiers, surtout utile pour des clés courtes)
+struct Fnv1aStream {
+    hasher: FnvHasher,
+}
+
+impl Fnv1aStream {
+    fn new() -> Self {
+        Fnv1aStream {
+            hasher: FnvHasher::default(),
+        }
+    }
+}
+
+impl HashingStream for Fnv1aStream {
+    fn update(&mut self, data: &[u8]) {
+        self.hasher.write(data);
+    }
+    fn finalize(&mut self) -> Vec<u8> {
+        self.hasher.finish().to_be_bytes().to_vec()
+    }
+}
+
+// Implémentation pour MurmurHash3 x64 128 bits, graine 0 (implémentation locale)
+struct Murmur3Stream {
+    hasher: Murmur3,
+}
+
+impl Murmur3Stream {
+    fn new() -> Self {
+        Murmur3Stream {
+            hasher: Murmur3::with_seed(0),
+        }
+    }
+}
+
+impl HashingStream for Murmur3Stream {
+    fn update(&mut self, data: &[u8]) {
+        self.hasher.update(data);
+    }
+    fn finalize(&mut self) -> Vec<u8> {
+        self.hasher.finalize().to_vec()
+    }
+}
+
+// Implémentation pour BLAKE2b de longueur choisie (HashConfig::blake2b_len)
+struct Blake2bVarStream {
+    hasher: Blake2bVar,
+    len: usize,
+}
+
+impl Blake2bVarStream {
+    fn new(len: usize) -> Self {
+        Blake2bVarStream {
+            // Longueur validée par l'appelant (1 à BLAKE2B_MAX_LEN)
+            hasher: Blake2bVar::new(len).expect("longueur BLAKE2b invalide"),
+            len,
+        }
+    }
+}
+
+impl HashingStream for Blake2bVarStream {
+    fn update(&mut self, data: &[u8]) {
+        Update::update(&mut self.hasher, data);
+    }
+    fn finalize(&mut self) -> Vec<u8> {
+        let mut out = vec![0; self.len];
+        let hasher = std::mem::replace(&mut self.hasher, Blake2bVar::new(self.len).unwrap());
+        hasher.finalize_variable(&mut out).unwrap();
+        out
+    }
+}
+
+// Implémentation pour BLAKE3
+struct Blake3Stream {
+    hasher: Blake3Hasher,
+    // Répartition sur le pool rayon permise (voir ReadGate)
+    parallel: bool,
+}
+
+impl Blake3Stream {
+    fn new(parallel: bool) -> Self {
+        Blake3Stream {
+            hasher: Blake3Hasher::new(),
+            parallel,
+        }
+    }
+}
+
+impl HashingStream for Blake3Stream {
+    fn update(&mut self, data: &[u8]) {
+        // update_rayon n'est rentable que sur les gros tampons
+        if self.parallel && data.len() >= BLAKE3_RAYON_THRESHOLD {
+            self.hasher.update_rayon(data);
+        } else {
+            self.hasher.update(data);
+        }
+    }
+    fn finalize(&mut self) -> Vec<u8> {
+        self.hasher.finalize().as_bytes().to_vec()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Petite limite et petit tampon : le streaming traverse plusieurs blocs
+    const LIMIT: u64 = 64 * 1024;
+    const BUFFER: usize = 4096;
+
+    fn sample(size: u64) -> Vec<u8> {
+        (0..size).map(|i| (i * 31 % 251) as u8).collect()
+    }
+
+    fn digest(path: &Path, algo: HashAlgo, full_load_limit: u64) -> Vec<u8> {
+        let config = HashConfig {
+            algo,
+            full_load_limit,
+            buffer_size: BUFFER,
+            ..HashConfig::default()
+        };
+        hash_file(path, &config).unwrap().bytes
+    }
+
+    #[test]
+    fn full_load_and_streaming_agree_across_limit() {
+        let dir = std::env::temp_dir().join(format!("zhashgen-test-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        for size in [0, 1, LIMIT - 1, LIMIT, LIMIT + 1, 3 * LIMIT + 7] {
+            let path = dir.join(format!("sample-{size}"));
+            fs::write(&path, sample(size)).unwrap();
+            for &algo in HashAlgo::value_variants() {
+                // Limite à la taille : chargement complet ; juste en dessous : streaming
+                let full = digest(&path, algo, size);
+                let streamed = digest(&path, algo, size.saturating_sub(1));
+                assert_eq!(full, streamed, "{}, {size} octets", algo.tag());
+                assert_eq!(
+                    full,
+                    digest(&path, algo, LIMIT),
+                    "{}, {size} octets",
+                    algo.tag()
+                );
+                assert_eq!(full.len() * 2, algo.hex_len(), "{}", algo.tag());
+            }
+        }
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn streaming_matches_one_shot_crates() {
+        // Tampon de 7 octets : les blocs ne tombent jamais sur une frontière de mot
+        let data = sample(3 * LIMIT + 7);
+        let streamed = |algo| {
+            let config = HashConfig {
+                algo,
+                buffer_size: 7,
+                ..HashConfig::default()
+            };
+            hash_reader(&data[..], &config).unwrap().bytes
+        };
+        let crc32c = crc32c::crc32c(&data).to_be_bytes();
+        assert_eq!(streamed(HashAlgo::Crc32c), crc32c);
+        assert_eq!(
+            streamed(HashAlgo::Crc32),
+            crc32fast::hash(&data).to_be_bytes()
+        );
+        assert_eq!(
+            streamed(HashAlgo::Crc64),
+            CRC64_XZ.checksum(&data).to_be_bytes()
+        );
+        assert_eq!(
+            streamed(HashAlgo::Xxh3),
+            xxhash_rust::xxh3::xxh3_64(&data).to_be_bytes()
+        );
+        assert_eq!(streamed(HashAlgo::Sha256), Sha256::digest(&data).to_vec());
+    }
+
+    #[test]
+    fn pipelined_matches_direct() {
+        let dir = std::env::temp_dir().join(format!("zhashgen-pipe-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("sample");
+        fs::write(&path, sample(3 * LIMIT + 7)).unwrap();
+        let pool = rayon::ThreadPoolBuilder::new()
+            .num_threads(2)
+            .build()
+            .unwrap();
+        let algos = HashAlgo::value_variants();
+        for full_load_limit in [0, LIMIT, u64::MAX] {
+            let config = HashConfig {
+                full_load_limit,
+                buffer_size: BUFFER,
+                ..HashConfig::default()
+            };
+            let direct = hash_file_multi(&path, algos, &config).unwrap();
+            let pipelined = hash_file_pipelined(&path, algos, &config, &pool).unwrap();
+            for ((algo, a), b) in algos.iter().zip(&direct).zip(&pipelined) {
+                assert_eq!(a.bytes, b.bytes, "{}", algo.tag());
+                assert_eq!(a.bytes, digest(&path, *algo, full_load_limit));
+            }
+        }
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn adler32_check_value() {
+        // Exemple de référence de la RFC 1950 (« Wikipedia » => 0x11E60398)
+        assert_eq!(
+            digest_slice(b"Wikipedia", HashAlgo::Adler32, &HashConfig::default()),
+            [0x11, 0xE6, 0x03, 0x98]
+        );
+    }
+
+    #[test]
+    fn fnv1a_and_murmur3_check_values() {
+        let config = HashConfig::default();
+        // Vecteurs de la spécification FNV et de SMHasher (graine 0)
+        assert_eq!(
+            digest_slice(b"a", HashAlgo::Fnv1a, &config),
+            0xaf63_dc4c_8601_ec8cu64.to_be_bytes()
+        );
+        assert_eq!(digest_slice(b"", HashAlgo::Murmur3, &config), [0; 16]);
+        let fox = b"The quick brown fox jumps over the lazy dog";
+        assert_eq!(
+            digest_slice(fox, HashAlgo::Murmur3, &config),
+            0x6c1b_07bc_7bbc_4be3_4793_9ac4_a93c_437au128.to_be_bytes()
+        );
+    }
+
+    // Valeur de contrôle (« check » du catalogue de Rocksoft) : CRC de "123456789"
+    fn crc32_check(poly: u32, init: u32, xorout: u32, reflected: bool) -> u32 {
+        let spec = Crc32Spec {
+            poly,
+            init,
+            xorout,
+            reflected,
+        };
+        let config = HashConfig {
+            crc32_table: Some(Box::leak(Box::new(Crc32Table::new(spec)))),
+            ..HashConfig::default()
+        };
+        let digest = digest_slice(b"123456789", HashAlgo::Crc32, &config);
+        u32::from_be_bytes(digest.try_into().unwrap())
+    }
+
+    #[test]
+    fn custom_crc32_polynomial() {
+        // Le polynôme IEEE redonne crc32fast, Castagnoli redonne CRC32C
+        let ieee = Crc32Spec::IEEE;
+        assert_eq!(crc32_check(ieee.poly, !0, !0, true), 0xCBF4_3926);
+        assert_eq!(crc32_check(0x1EDC_6F41, !0, !0, true), 0xE306_9283);
+    }
+
+    #[test]
+    fn crc32_init_and_xorout() {
+        let poly = Crc32Spec::IEEE.poly;
+        // CRC-32/BZIP2, CRC-32/MPEG-2, CRC-32/POSIX (cksum), CRC-32/JAMCRC
+        assert_eq!(crc32_check(poly, !0, !0, false), 0xFC89_1918);
+        assert_eq!(crc32_check(poly, !0, 0, false), 0x0376_E6E7);
+        assert_eq!(crc32_check(poly, 0, !0, false), 0x765E_7680);
+        assert_eq!(crc32_check(poly, !0, 0, true), 0x340B_C6D9);
+        // CRC-32/XFER : ni valeur initiale ni XOR final
+        assert_eq!(crc32_check(0x0000_00AF, 0, 0, false), 0xBD0B_E338);
+    }
+
+    #[test]
+    fn crc16_check_values() {
+        // Valeurs de contrôle du catalogue de Rocksoft, en flux comme d'un bloc
+        let data = b"123456789";
+        for (crc16, check) in [
+            (Crc16Variant::Ccitt, 0x29B1u16),
+            (Crc16Variant::Modbus, 0x4B37),
+            (Crc16Variant::Xmodem, 0x31C3),
+        ] {
+            let config = HashConfig {
+                algo: HashAlgo::Crc16,
+                crc16,
+                buffer_size: 2,
+                ..HashConfig::default()
+            };
+            let check = check.to_be_bytes();
+            assert_eq!(digest_slice(data, HashAlgo::Crc16, &config), check);
+            assert_eq!(hash_reader(&data[..], &config).unwrap().bytes, check);
+        }
+    }
+
+    #[test]
+    fn blake2b_variable_length() {
+        // BLAKE2b-256("abc"), comme b2sum -l 256
+        let config = HashConfig {
+            blake2b_len: 32,
+            ..HashConfig::default()
+        };
+        let hex: String = digest_slice(b"abc", HashAlgo::Blake2b, &config)
+            .iter()
+            .map(|b| format!("{b:02x}"))
+            .collect();
+        assert_eq!(
+            hex,
+            "bddd813c634239723171ef3fee98579b94964e3bb1cb3e427262c8c068d52319"
+        );
+        // Sur 64 octets, Blake2bVar redonne Blake2b512
+        let mut var = Blake2bVarStream::new(BLAKE2B_MAX_LEN);
+        var.update(b"abc");
+        assert_eq!(var.finalize(), Blake2b512::digest(b"abc").to_vec());
+    }
+
+    #[test]
+    fn crc32c_check_value() {
+        // Valeur de contrôle du CRC-32C (Castagnoli) pour "123456789"
+        assert_eq!(
+            digest_slice(b"123456789", HashAlgo::Crc32c, &HashConfig::default()),
+            [0xE3, 0x06, 0x92, 0x83]
+        );
+    }
+}
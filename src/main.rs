@@ -1,376 +1,4472 @@
-use cityhash::city_hash_128;
+mod config;
+mod messages;
+
+use base64::{Engine, engine::general_purpose::STANDARD as BASE64};
 use clap::{Parser, ValueEnum};
-use crc32c::crc32c; // Pour CRC32C (Castagnoli)
-use crc32fast::Hasher as Crc32Hasher;
-use indicatif::{ProgressBar, ProgressStyle};
+use fs2::FileExt;
+use globset::{Glob, GlobSet, GlobSetBuilder};
+use ignore::{
+    WalkBuilder,
+    gitignore::{Gitignore, GitignoreBuilder},
+};
+use indicatif::{HumanDuration, ProgressBar, ProgressDrawTarget, ProgressState, ProgressStyle};
+use log::{LevelFilter, debug, error, info, trace};
+use messages::{Lang, Msg};
+use notify::{Event, EventKind, RecursiveMode, Watcher};
 use rayon::prelude::*;
+use serde::Serialize;
+use sha2::{Digest, Sha256};
 use std::{
+    collections::{BTreeMap, HashMap, HashSet},
     fs::{self, File},
-    io::{self, Read, Write},
+    io::{self, BufWriter, IsTerminal, Read, Write},
     path::{Path, PathBuf},
-    time::Instant,
+    sync::{
+        Arc, OnceLock,
+        atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering},
+        mpsc,
+    },
+    thread,
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
 };
+use unicode_normalization::UnicodeNormalization;
 use walkdir::WalkDir;
-use xxhash_rust::xxh3::Xxh3;
-
-// Imports pour les nouveaux algorithmes
-use blake2::{Blake2b512, Blake2s256};
-use sha2::{Digest, Sha256, Sha512};
-use once_cell::sync::Lazy;
-
-// La taille du tampon pour le mode streaming (1 MiB)
-const BUFFER_SIZE: usize = 1024 * 1024;
-const DEFAULT_FULL_LOAD_LIMIT: u64 = 200 * 1024 * 1024;
-
-// CRC32C (Castagnoli) lookup table (reflected polynomial 0x82F63B78)
-static CRC32C_TABLE: Lazy<[u32; 256]> = Lazy::new(|| {
-    let poly: u32 = 0x82F63B78u32;
-    let mut table = [0u32; 256];
-    for i in 0..256u32 {
-        let mut c = i;
-        for _ in 0..8 {
-            if (c & 1) != 0 {
-                c = poly ^ (c >> 1);
-            } else {
-                c = c >> 1;
-            }
-        }
-        table[i as usize] = c;
-    }
-    table
-});
+use zhashgen::{
+    BLAKE2B_MAX_LEN, Compression, Crc16Variant, Crc32Spec, Crc32Table, DEFAULT_BUFFER_SIZE,
+    DEFAULT_FULL_LOAD_LIMIT, ErrorClass, FileDigest, HashAlgo, HashConfig, ReadGate, hash_file,
+    hash_file_multi, hash_file_pipelined, hash_reader, long_path, strip_long_path,
+};
 
 #[derive(Parser)]
 struct Args {
+    // Fichier de configuration TOML (par défaut ./newcrc.toml s'il existe) ; les options de
+    // la ligne de commande l'emportent sur ses valeurs
+    #[arg(long, value_name = "FILE")]
+    config: Option<PathBuf>,
+    // Répertoire à parcourir, ou un fichier seul / "-" (stdin) dont le digest est affiché
     #[arg(short, long, default_value = ".")]
     source: PathBuf,
     #[arg(short, long, default_value = "./Hashes")]
     output_dir: PathBuf,
     #[arg(short, long, default_value = "checksums.txt")]
     name: String,
+    // Un manifeste --name dans chaque répertoire de --source contenant des fichiers, chemins
+    // relatifs à ce répertoire, au lieu d'un manifeste unique dans --output-dir
+    #[arg(long, conflicts_with_all = ["output", "watch", "update", "files_from", "path_base"])]
+    per_dir: bool,
+    // Complète le manifeste existant (texte ou SFV) : seuls les fichiers qui n'y figurent
+    // pas encore sont hachés et ajoutés à la fin, sans réécrire les lignes présentes
+    #[arg(
+        long,
+        conflicts_with_all = ["update", "watch", "per_dir", "manifest_checksum", "template"]
+    )]
+    append: bool,
+    // Chemin complet du manifeste (remplace --output-dir/--name), "-" pour stdout
+    #[arg(long, value_name = "FILE")]
+    output: Option<PathBuf>,
+    // Fichiers illisibles des manifestes texte et SFV, un « [ERROR] chemin: message » par
+    // ligne : <manifeste>.errors par défaut (stderr si le manifeste va sur stdout), "-"
+    // pour stderr. JSON et CSV gardent leurs champs d'erreur.
+    #[arg(long, value_name = "FILE")]
+    errors_file: Option<PathBuf>,
+    // Liste de chemins à hacher (un par ligne ou séparés par NUL, "-" pour stdin) au lieu
+    // de parcourir --source
+    #[arg(long, value_name = "FILE")]
+    files_from: Option<PathBuf>,
+    // Motifs glob (répétables) évalués sur le chemin relatif à --source :
+    // --include restreint la sélection, --exclude en retire
+    #[arg(long, value_name = "GLOB")]
+    include: Vec<String>,
+    #[arg(long, value_name = "GLOB")]
+    exclude: Vec<String>,
+    // Exclusions propres à zhashgen, syntaxe .gitignore (un motif par ligne, ! pour
+    // réinclure, / final pour un répertoire) ; par défaut <--source>/.newcrcignore s'il existe
+    #[arg(long, value_name = "PATH")]
+    ignore_file: Option<PathBuf>,
+    // Parcours via le crate `ignore` : respecte .gitignore, .ignore et les exclusions git globales
+    #[arg(long)]
+    respect_gitignore: bool,
+    // Bornes de taille (ex. 10MiB, 1GB) : les fichiers hors intervalle sont ignorés
+    #[arg(long, value_name = "SIZE", value_parser = parse_size)]
+    min_size: Option<u64>,
+    #[arg(long, value_name = "SIZE", value_parser = parse_size)]
+    max_size: Option<u64>,
+    // Garde-fous du parcours (ex. --source / par erreur) : arrêt avant tout hachage dès
+    // que le nombre de fichiers retenus ou leur taille cumulée dépasse la limite
+    #[arg(long, value_name = "N")]
+    max_files: Option<usize>,
+    #[arg(long, value_name = "SIZE", value_parser = parse_size)]
+    max_total_size: Option<u64>,
+    // Extensions autorisées, insensibles à la casse (ex. --ext jpg,png,raw)
+    #[arg(long, value_name = "EXT", value_delimiter = ',')]
+    ext: Vec<String>,
+    // Profondeur de parcours : 1 = enfants directs de --source uniquement
+    #[arg(long, value_name = "N")]
+    max_depth: Option<usize>,
+    #[arg(long, value_name = "N")]
+    min_depth: Option<usize>,
+    // Suit les liens symboliques (répertoires compris), chaque répertoire réel n'est visité qu'une fois
+    #[arg(long)]
+    follow_symlinks: bool,
+    // Enregistre les liens symboliques sans les suivre : leur digest est celui du chemin
+    // cible (octets de readlink), suivi d'une colonne « symlink -> cible ». À repasser à --check.
+    #[arg(long, conflicts_with = "follow_symlinks")]
+    include_symlinks_as_entries: bool,
+    // Ignore les fichiers et répertoires cachés (nom commençant par '.'), sans y descendre
+    #[arg(long)]
+    skip_hidden: bool,
+    // Écarte les fichiers vides (tous de même digest, ils encombrent les doublons) ;
+    // leur nombre figure dans le bilan, écartés ou non
+    #[arg(long)]
+    skip_empty: bool,
     // Limite au-dessus de laquelle on passe en mode streaming pour économiser la RAM
     #[arg(long, default_value_t = DEFAULT_FULL_LOAD_LIMIT)]
     full_load_limit: u64,
+    // Petits fichiers lus par projection mémoire plutôt que copiés dans un Vec. Gagnant
+    // à partir de quelques centaines de Kio ; en dessous, le coût de la projection domine
+    // (voir benches/throughput.rs)
+    #[arg(long)]
+    mmap: bool,
+    // Découpe les gros fichiers en blocs hachés en parallèle (BLAKE3 et CRC32 uniquement,
+    // les autres algorithmes restent séquentiels)
+    #[arg(long)]
+    intra_file_parallel: bool,
+    // Tampon de lecture du mode streaming (ex. 64KiB, 16MiB) ; une puissance de deux
+    // est conseillée, seule la valeur nulle est refusée
+    #[arg(long, value_name = "SIZE", value_parser = parse_buffer_size, default_value = "1MiB")]
+    buffer_size: usize,
     #[arg(long, default_value_t = num_cpus::get())]
     threads: usize,
-    #[arg(long, value_enum, default_value_t = HashAlgo::Xxh3)]
-    algo: HashAlgo,
+    // Pools séparés : fichiers lus en parallèle et threads de hachage des blocs lus.
+    // Sans ces options, un seul pool (--threads) lit et hache.
+    #[arg(long, value_name = "N")]
+    read_threads: Option<usize>,
+    #[arg(long, value_name = "N")]
+    hash_threads: Option<usize>,
+    // hdd : au plus deux fichiers lus à la fois quel que soit --threads (le hachage reste
+    // parallèle) ; auto : hdd si le disque de --source est rotatif (Linux)
+    #[arg(long, value_enum, default_value_t = IoProfile::Ssd)]
+    io_profile: IoProfile,
+    // skip : l'erreur est consignée dans le manifeste et le parcours continue ;
+    // fail : arrêt au premier fichier illisible, code de sortie non nul
+    #[arg(long, value_enum, default_value_t = OnError::Skip)]
+    on_error: OnError,
+    // Une panique pendant le hachage d'un fichier (crate d'algorithme, décompression...)
+    // devient une erreur de ce fichier, traitée selon --on-error, au lieu d'arrêter le
+    // programme
+    #[arg(long)]
+    continue_on_panic: bool,
+    // Quitte sans attendre Entrée (automatique si stdin n'est pas un terminal)
+    #[arg(long)]
+    no_pause: bool,
+    // Supprime le bloc de statistiques final
+    #[arg(long)]
+    quiet: bool,
+    // Journal sur stderr : -v fichiers hachés et erreurs, -vv raisons d'exclusion,
+    // -vvv durée par fichier
+    #[arg(short, long, action = clap::ArgAction::Count)]
+    verbose: u8,
+    // Langue des messages (statistiques, mode interactif), aussi via ZHASHGEN_LANG
+    #[arg(long, value_enum, env = "ZHASHGEN_LANG", default_value_t = Lang::En)]
+    lang: Lang,
+    // Désactive la barre de progression (automatique si stderr n'est pas un terminal)
+    #[arg(long)]
+    no_progress: bool,
+    // json : un objet {"done","total","path","bytes"} par fichier terminé sur stderr, à la
+    // place de la barre (même si stderr n'est pas un terminal), pour une interface externe
+    #[arg(long, value_enum, default_value_t = ProgressFormat::Bar)]
+    progress_format: ProgressFormat,
+    // Reste actif après la génération et met à jour le manifeste quand des fichiers de
+    // --source sont créés, modifiés ou supprimés (filtres respectés), jusqu'à Ctrl-C
+    #[arg(long)]
+    watch: bool,
+    // Un ou plusieurs algorithmes séparés par des virgules (xxh3,sha256), calculés en une
+    // seule lecture. Le premier sert au digest principal (--check, --update, doublons...),
+    // les suivants ajoutent des colonnes au manifeste.
+    #[arg(long, value_enum, value_delimiter = ',', default_value = "xxh3")]
+    algo: Vec<HashAlgo>,
+    // Paramètres de --algo crc32, par défaut ceux de CRC-32 IEEE. Valeurs hexadécimales ;
+    // polynôme en notation normale (bit de poids fort en tête, x^32 implicite, ex. 1EDC6F41).
+    // --crc-init est la valeur initiale du registre et --crc-xorout le XOR final, tels que
+    // publiés dans les catalogues de CRC ; --crc-reflect false traite les octets bit de
+    // poids fort en tête (CRC-32/BZIP2, MPEG-2, POSIX). Digest écrit en big-endian.
+    #[arg(long, value_name = "HEX", value_parser = parse_crc32_hex, default_value = "04C11DB7")]
+    crc_poly: u32,
+    #[arg(long, value_name = "HEX", value_parser = parse_crc32_hex, default_value = "FFFFFFFF")]
+    crc_init: u32,
+    #[arg(long, value_name = "HEX", value_parser = parse_crc32_hex, default_value = "FFFFFFFF")]
+    crc_xorout: u32,
+    #[arg(long, value_name = "BOOL", action = clap::ArgAction::Set, default_value_t = true)]
+    crc_reflect: bool,
+    // Variante de --algo crc16 : ccitt (CRC-16/IBM-3740, ex-CCITT-FALSE), modbus ou xmodem.
+    // Non déductible d'un manifeste : à redonner pour --check.
+    #[arg(long, value_enum, default_value = "ccitt")]
+    crc16_variant: Crc16Variant,
+    // Longueur du digest de --algo blake2b, en octets (1 à 64). Chaque longueur donne un
+    // digest différent, pas une troncature : 32 correspond au BLAKE2b-256 d'autres outils.
+    // À redonner pour --check.
+    #[arg(long, value_name = "N", value_parser = parse_blake2b_length, default_value = "64")]
+    blake2b_length: usize,
+    // HMAC avec cette clé au lieu du digest nu (md5, sha1, sha2, sha3, blake2 uniquement),
+    // à redonner pour --check. --hmac-key-file lit la clé dans un fichier (sans le saut de
+    // ligne final), ce qui évite qu'elle apparaisse dans la liste des processus.
+    #[arg(long, value_name = "KEY", conflicts_with = "hmac_key_file")]
+    hmac_key: Option<String>,
+    #[arg(long, value_name = "FILE")]
+    hmac_key_file: Option<PathBuf>,
+    // Vérifie les fichiers listés dans un manifeste existant au lieu d'en générer un.
+    // Un répertoire : vérifie chaque manifeste --name qu'il contient (arborescence --per-dir)
+    #[arg(short, long, value_name = "FILE")]
+    check: Option<PathBuf>,
+    // Comme --check avec un manifeste distant (miroir comparé à la liste amont) : seul le
+    // manifeste est téléchargé, les fichiers sont ceux de --source
+    #[arg(long, value_name = "URL", conflicts_with = "check")]
+    check_url: Option<String>,
+    // Compare --source à un autre arbre (ex. une sauvegarde) : fichiers ajoutés, supprimés
+    // ou modifiés, repérés par chemin relatif
+    #[arg(long, value_name = "OTHER_DIR")]
+    compare: Option<PathBuf>,
+    // Liste les fichiers retenus par les filtres, sans les ouvrir ni les hacher : chemins
+    // sur stdout (ou dans --output), nombre et taille totale sur stderr
+    #[arg(long)]
+    dry_run: bool,
+    // Mesure le débit de chaque algorithme en streaming, sur --source s'il désigne un fichier,
+    // sinon sur des données synthétiques de --bench-size
+    #[arg(long)]
+    bench: bool,
+    #[arg(long, value_name = "SIZE", value_parser = parse_buffer_size, default_value = "64MiB")]
+    bench_size: usize,
+    // Passes mesurées par algorithme, après une passe de chauffe
+    #[arg(long, value_name = "N", default_value_t = 5)]
+    bench_iterations: u32,
+    // Rapport de vérification : lignes OK/FAILED ou objet JSON sur stdout
+    #[arg(long, value_enum, default_value_t = CheckFormat::Text)]
+    check_format: CheckFormat,
+    // Format du manifeste généré
+    #[arg(long, value_enum, default_value_t = OutputFormat::Text)]
+    format: OutputFormat,
+    // Disposition des lignes du format texte
+    #[arg(long, value_enum, default_value_t = OutputStyle::Legacy)]
+    style: OutputStyle,
+    // Disposition libre des lignes texte, ex. "{digest} {size} {path}" : champs {digest},
+    // {path}, {size}, {algo} et {mtime}, {{ et }} pour une accolade. Une ligne par algorithme ;
+    // remplace --style. Un tel manifeste n'est pas relu par --check.
+    #[arg(long, value_parser = parse_template, conflicts_with = "with_meta")]
+    template: Option<Template>,
+    // Hache le contenu décompressé des fichiers .gz, .zst et .bz2 (les autres restent bruts) ;
+    // le manifeste le signale, et --check en tient compte
+    #[arg(long)]
+    decompress: bool,
+    // Ne hache que les N premiers octets de chaque fichier (ex. 1MiB) : pré-filtre rapide
+    // de doublons, à confirmer par un hachage complet. La taille enregistrée reste celle du
+    // fichier ; le manifeste signale ses digests partiels et --check en tient compte.
+    #[arg(
+        long,
+        value_name = "SIZE",
+        value_parser = parse_size,
+        conflicts_with_all = ["decompress", "update", "append"]
+    )]
+    head: Option<u64>,
+    // Hache le mode (permissions), l'uid/gid et la taille avant le contenu : un changement
+    // d'attributs modifie le digest (référentiel de sécurité). Ces digests ne se comparent
+    // pas à ceux du contenu seul : le manifeste le signale et --check en tient compte.
+    // --update est exclu : un chmod ne change pas la date de modification.
+    #[arg(long, conflicts_with_all = ["decompress", "update", "append"])]
+    include_metadata_in_hash: bool,
+    // Termine chaque ligne par un octet NUL au lieu d'un saut de ligne (pour `xargs -0`) :
+    // manifeste texte sur stdout (--output -) ou liste --dry-run uniquement
+    #[arg(long)]
+    print0: bool,
+    // Fin de ligne des manifestes : LF par défaut, pour des manifestes identiques d'une
+    // plateforme à l'autre ; native vaut CRLF sous Windows. --check accepte les deux.
+    #[arg(long, value_enum, default_value_t = LineEnding::Lf)]
+    line_ending: LineEnding,
+    // Termine le manifeste texte ou SFV par « # manifest-sha256: <digest> » (« ; » en SFV),
+    // calculé sur tout ce qui précède ; --check le contrôle avant de lire les entrées
+    #[arg(long)]
+    manifest_checksum: bool,
+    // Ajoute la taille et la date de modification (RFC3339) : colonnes séparées par des
+    // tabulations en fin de ligne texte, champ `modified` en JSON, colonne en CSV
+    #[arg(long)]
+    with_meta: bool,
+    // Format texte : chemins complétés d'espaces jusqu'au plus long, pour aligner les
+    // colonnes qui les suivent (--with-meta, algorithmes supplémentaires...). Purement
+    // visuel : --check lirait ces espaces comme faisant partie du nom.
+    #[arg(long)]
+    align: bool,
+    // Durée (ms) et débit (MB/s) de chaque fichier dans les sorties JSON et CSV
+    #[arg(long)]
+    timings: bool,
+    // Regroupe les fichiers de digest identique et liste les doublons sur stdout
+    #[arg(long)]
+    find_dupes: bool,
+    // Rapport des doublons dans un fichier plutôt que sur stdout (implique --find-dupes)
+    #[arg(long, value_name = "FILE")]
+    dupes_report: Option<PathBuf>,
+    // Remplace chaque doublon par un lien physique vers le premier fichier de son groupe,
+    // après comparaison octet par octet et sur le même système de fichiers seulement. Le
+    // plan est toujours affiché d'abord, puis confirmé au terminal (ou par --yes) ; le
+    // doublon prend les droits et la date du fichier conservé.
+    #[arg(long, conflicts_with = "watch")]
+    hardlink_dupes: bool,
+    // Applique --hardlink-dupes sans demander confirmation
+    #[arg(long, requires = "hardlink_dupes")]
+    yes: bool,
+    // Parcourt aussi --output-dir lorsqu'il se trouve sous --source (les fichiers produits
+    // par ce passage restent exclus)
+    #[arg(long)]
+    no_output_exclude: bool,
+    // Écrit le manifeste même si <manifeste>.lock ne peut être verrouillé (verrou laissé
+    // par un partage réseau sans support des verrous, par exemple)
+    #[arg(long)]
+    force: bool,
+    // Reprend le manifeste existant : les fichiers dont la taille et la date de modification
+    // n'ont pas changé gardent leur digest (état conservé dans <manifeste>.state)
+    #[arg(long)]
+    update: bool,
+    // Préfixe ajouté devant chaque chemin enregistré (ex. "../" pour l'ancien format)
+    #[arg(long, default_value = "")]
+    path_prefix: String,
+    // Base des chemins enregistrés : relatifs à --source, au répertoire courant, ou absolus.
+    // --check résout les chemins relatifs depuis --source (utiliser -s . avec cwd).
+    #[arg(long, value_enum, default_value_t = PathBase::Source)]
+    path_base: PathBase,
+    // Forme Unicode des chemins enregistrés. macOS rend les noms décomposés (NFD) là où
+    // Linux et Windows les gardent en général composés (NFC) : --normalize-paths nfc sur
+    // macOS donne un manifeste vérifiable ailleurs. Par défaut, octets repris tels quels.
+    #[arg(long, value_enum, default_value_t = PathNormalization::None)]
+    normalize_paths: PathNormalization,
+    // Encodage des digests, en écriture comme en vérification
+    #[arg(long, value_enum, default_value_t = Encoding::Hex)]
+    encoding: Encoding,
+    // Hexadécimal en majuscules (sans effet en base64) ; --check reste insensible à la casse
+    #[arg(long)]
+    uppercase: bool,
+    // Ne garde que les N premiers caractères hexadécimaux de chaque digest (identifiants
+    // courts). En --check, chaque digest est comparé sur sa longueur enregistrée et
+    // l'algorithme est celui de --algo.
+    #[arg(long, value_name = "N")]
+    truncate: Option<usize>,
+    // Ordre des entrées du manifeste : chemin (défaut), taille décroissante ou digest
+    // croissant, inversé par --reverse. Hors tri par chemin, le manifeste n'est écrit qu'une
+    // fois tous les fichiers hachés.
+    #[arg(long, value_enum, default_value_t = SortKey::Path, conflicts_with_all = ["per_dir", "watch"])]
+    sort: SortKey,
+    #[arg(long, conflicts_with_all = ["per_dir", "watch"])]
+    reverse: bool,
+}
+
+impl Args {
+    // Algorithme du digest principal
+    fn primary_algo(&self) -> HashAlgo {
+        self.algo[0]
+    }
+
+    // Longueur hexadécimale produite avec ces options (--blake2b-length)
+    fn hex_len(&self, algo: HashAlgo) -> usize {
+        match algo {
+            HashAlgo::Blake2b => self.blake2b_length * 2,
+            _ => algo.hex_len(),
+        }
+    }
+
+    // Algorithmes supplémentaires, dans l'ordre de --algo
+    fn extra_algos(&self) -> &[HashAlgo] {
+        &self.algo[1..]
+    }
+
+    fn crc32_spec(&self) -> Crc32Spec {
+        Crc32Spec {
+            poly: self.crc_poly,
+            init: self.crc_init,
+            xorout: self.crc_xorout,
+            reflected: self.crc_reflect,
+        }
+    }
+
+    // Clé de --hmac-key ou --hmac-key-file, refusée vide
+    fn load_hmac_key(&self) -> io::Result<Option<Vec<u8>>> {
+        let key = match (&self.hmac_key, &self.hmac_key_file) {
+            (Some(key), _) => key.as_bytes().to_vec(),
+            (None, Some(path)) => {
+                let mut key = fs::read(path)?;
+                if key.ends_with(b"\n") {
+                    key.pop();
+                    if key.ends_with(b"\r") {
+                        key.pop();
+                    }
+                }
+                key
+            }
+            (None, None) => return Ok(None),
+        };
+        if key.is_empty() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                Msg::EmptyHmacKey.text(self.lang),
+            ));
+        }
+        Ok(Some(key))
+    }
+
+    fn hash_config(&self) -> HashConfig {
+        HashConfig {
+            algo: self.primary_algo(),
+            full_load_limit: self.full_load_limit,
+            mmap: self.mmap,
+            intra_file_parallel: self.intra_file_parallel,
+            buffer_size: self.buffer_size,
+            read_gate: READ_GATE.get(),
+            decompress: self.decompress,
+            crc32_table: CRC32_TABLE.get(),
+            crc16: self.crc16_variant,
+            blake2b_len: self.blake2b_length,
+            hmac_key: HMAC_KEY.get().map(Vec::as_slice),
+            head: self.head,
+            metadata: self.include_metadata_in_hash,
+        }
+    }
 }
 
 #[derive(Copy, Clone, ValueEnum)]
-enum HashAlgo {
-    // Hashes légers et rapides (non-cryptographiques)
-    Crc32,
-    Crc32c,
-    City128,
-    Xxh3,
-    // Hashes cryptographiques (plus lents, plus sécurisés)
-    Sha256,
-    Sha512,
-    Blake2b,
-    Blake2s,
-}
-
-fn main() -> std::io::Result<()> {
+enum OutputFormat {
+    // digest *chemin (format historique)
+    Text,
+    // Tableau d'objets { path, digest, size, error }
+    Json,
+    // path,algo,digest,size_bytes,error (une ligne par fichier)
+    Csv,
+    // Simple File Verification : chemin CRC32 (8 hex majuscules), --algo crc32 uniquement
+    Sfv,
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, ValueEnum)]
+enum OnError {
+    Skip,
+    Fail,
+}
+
+#[derive(Copy, Clone, ValueEnum)]
+enum PathBase {
+    Source,
+    Cwd,
+    Absolute,
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, ValueEnum)]
+enum PathNormalization {
+    Nfc,
+    Nfd,
+    None,
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug, ValueEnum)]
+enum IoProfile {
+    Ssd,
+    Hdd,
+    Auto,
+}
+
+#[derive(Copy, Clone, ValueEnum)]
+enum Encoding {
+    Hex,
+    // Alphabet standard avec remplissage '=' (plus compact pour les manifestes web)
+    Base64,
+}
+
+#[derive(Copy, Clone, ValueEnum)]
+enum CheckFormat {
+    Text,
+    Json,
+}
+
+#[derive(Copy, Clone, ValueEnum)]
+enum OutputStyle {
+    // digest *chemin (NewCrc historique)
+    Legacy,
+    // digest  chemin (deux espaces, séparateurs /) compatible `sha256sum -c`
+    Gnu,
+    // ALGO (chemin) = digest, comme `sha256sum --tag` ; une ligne par algorithme
+    Bsd,
+}
+
+#[derive(Copy, Clone, ValueEnum)]
+enum LineEnding {
+    Lf,
+    Crlf,
+    Native,
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, ValueEnum)]
+enum SortKey {
+    Path,
+    // Plus gros d'abord
+    Size,
+    Digest,
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, ValueEnum)]
+enum ProgressFormat {
+    Bar,
+    Json,
+}
+
+// Lectures simultanées sur disque rotatif (--io-profile hdd)
+const HDD_CONCURRENT_READS: usize = 2;
+
+// Limite de lectures partagée par tous les hachages, fixée au démarrage selon --io-profile
+static READ_GATE: OnceLock<ReadGate> = OnceLock::new();
+
+// Table des paramètres --crc-*, construite au démarrage s'ils diffèrent de CRC-32 IEEE
+static CRC32_TABLE: OnceLock<Crc32Table> = OnceLock::new();
+
+// Clé de --hmac-key / --hmac-key-file, lue au démarrage
+static HMAC_KEY: OnceLock<Vec<u8>> = OnceLock::new();
+
+// Largeur de la colonne des chemins (--align), en caractères, calculée avant le hachage
+static PATH_WIDTH: OnceLock<usize> = OnceLock::new();
+
+// --lang, connu une fois les options lues (voir current_lang)
+static LANG: OnceLock<Lang> = OnceLock::new();
+
+// Langue des messages là où `Args` n'est pas accessible : --lang une fois les options lues,
+// sinon (erreurs des value_parser, pendant l'analyse) telle que clap la lira
+fn current_lang() -> Lang {
+    LANG.get().copied().unwrap_or_else(|| {
+        // Arguments non UTF-8 ignorés : std::env::args paniquerait
+        let mut args = std::env::args_os()
+            .skip(1)
+            .map(|arg| arg.into_string().ok());
+        let mut value = None;
+        while let Some(arg) = args.next() {
+            match arg.as_deref() {
+                Some("--") => break,
+                Some("--lang") => value = args.next().flatten(),
+                Some(arg) => {
+                    if let Some(lang) = arg.strip_prefix("--lang=") {
+                        value = Some(lang.to_string());
+                    }
+                }
+                None => {}
+            }
+        }
+        value
+            .or_else(|| std::env::var("ZHASHGEN_LANG").ok())
+            .and_then(|value| Lang::from_str(&value, true).ok())
+            .unwrap_or(Lang::En)
+    })
+}
+
+// Positionné par Ctrl-C : plus aucun fichier n'est lancé, les résultats obtenus sont écrits
+static INTERRUPTED: AtomicBool = AtomicBool::new(false);
+
+// Codes de sortie : 0 si tout a réussi, EXIT_FILE_ERRORS si des fichiers n'ont pas pu
+// être hachés, EXIT_CHECK_FAILED si --check, --check-url ou --compare trouvent une
+// différence, EXIT_FATAL sur erreur fatale (options, configuration, manifeste illisible...)
+const EXIT_FILE_ERRORS: i32 = 1;
+const EXIT_CHECK_FAILED: i32 = 2;
+const EXIT_FATAL: i32 = 3;
+// Processus interrompu par SIGINT (128 + 2)
+const EXIT_INTERRUPTED: i32 = 130;
+
+fn main() {
+    if let Err(e) = run() {
+        eprintln!("{} {e}", Msg::Error.text(current_lang()));
+        std::process::exit(EXIT_FATAL);
+    }
+}
+
+fn run() -> io::Result<()> {
     let use_interactive = std::env::args().len() == 1;
 
     let args = if use_interactive {
         get_interactive_args()?
     } else {
-        Args::parse()
+        config::parse_args()?
     };
+    LANG.get_or_init(|| args.lang);
+
+    let level = match args.verbose {
+        0 => LevelFilter::Off,
+        1 => LevelFilter::Info,
+        2 => LevelFilter::Debug,
+        _ => LevelFilter::Trace,
+    };
+    env_logger::Builder::new()
+        .filter_level(level)
+        .parse_default_env()
+        .format_target(false)
+        .init();
 
     rayon::ThreadPoolBuilder::new()
         .num_threads(args.threads)
         .build_global()
         .unwrap();
 
-    fs::create_dir_all(&args.output_dir)?;
-    let output_file = args.output_dir.join(&args.name);
+    let io_profile = match args.io_profile {
+        IoProfile::Auto if is_rotational(&args.source) == Some(true) => IoProfile::Hdd,
+        IoProfile::Auto => IoProfile::Ssd,
+        profile => profile,
+    };
+    info!("{} {io_profile:?}", Msg::IoProfile.text(args.lang));
+    if io_profile == IoProfile::Hdd {
+        READ_GATE.get_or_init(|| ReadGate::new(HDD_CONCURRENT_READS));
+    }
+    let crc_spec = args.crc32_spec();
+    if crc_spec != Crc32Spec::IEEE {
+        info!("CRC32 : {crc_spec:X?}");
+        CRC32_TABLE.get_or_init(|| Crc32Table::new(crc_spec));
+    }
+    if let Some(key) = args.load_hmac_key()? {
+        // En vérification, les algorithmes sont ceux du manifeste : contrôlés par check_content
+        if args.check.is_none() && args.check_url.is_none() {
+            check_hmac_algos(&args.algo, &args)?;
+        }
+        HMAC_KEY.get_or_init(|| key);
+    }
+    if let Some(len) = args.truncate {
+        check_truncate(&args, len)?;
+    }
 
-    let files: Vec<_> = WalkDir::new(&args.source)
-        .into_iter()
-        .filter_map(|e| e.ok())
-        .filter(|e| e.file_type().is_file())
-        // Filtrer le fichier de sortie lui-même
-        .filter(|e| {
-            e.path()
-                .canonicalize()
-                .unwrap_or_else(|_| e.path().to_path_buf())
-                != output_file
-                    .canonicalize()
-                    .unwrap_or_else(|_| output_file.to_path_buf())
-        })
-        .map(|e| e.path().to_path_buf())
-        .collect();
+    if let Some(manifest) = &args.check {
+        if !run_check(&args, manifest)? {
+            std::process::exit(EXIT_CHECK_FAILED);
+        }
+        return Ok(());
+    }
+    if let Some(url) = &args.check_url {
+        if !run_check_url(&args, url)? {
+            std::process::exit(EXIT_CHECK_FAILED);
+        }
+        return Ok(());
+    }
 
-    let pb = ProgressBar::new(files.len() as u64);
-    pb.set_style(
-        ProgressStyle::with_template("[{elapsed_precise}] {bar:40.cyan/blue} {pos}/{len} {msg}")
-            .unwrap()
-            .progress_chars("##-"),
-    );
+    if args.bench {
+        return run_bench(&args);
+    }
 
-    let start = Instant::now();
-    let results: Vec<_> = files
-        .par_iter()
-        .map(|path| {
-            let res = match hash_file(path, args.full_load_limit, args.algo) {
-                Ok((digest, size)) => {
-                    // Calcul du chemin relatif
-                    let rel = path.strip_prefix(&args.source).unwrap_or(path);
-                    // Format standard du fichier de checksum (digest *chemin)
-                    (format!("{digest} *..\\{}\n", rel.display()), size, 0)
-                }
-                Err(e) => (format!("[ERROR] {}: {}\n", path.display(), e), 0, 1),
-            };
-            pb.inc(1);
-            res
-        })
-        .collect();
-    pb.finish();
+    if let Some(other) = &args.compare {
+        if !run_compare(&args, other)? {
+            std::process::exit(EXIT_CHECK_FAILED);
+        }
+        return Ok(());
+    }
 
-    let mut out = File::create(&output_file)?;
-    let (mut total_bytes, mut total_errors) = (0u64, 0u64);
-    for (line, size, err) in &results {
-        out.write_all(line.as_bytes())?;
-        total_bytes += *size;
-        total_errors += *err as u64;
-    }
-
-    let elapsed = start.elapsed().as_secs_f64();
-    println!("\nDone! Hashes saved to: {}", output_file.display());
-    println!("=== Statistiques ===");
-    println!("Fichiers traités     : {}", files.len());
-    println!("Erreurs              : {}", total_errors);
-    println!("Volume total         : {}", human_readable(total_bytes));
-    println!("Temps écoulé         : {:.2} s", elapsed);
-    println!(
-        "Débit moyen          : {}/s",
-        human_readable((total_bytes as f64 / elapsed) as u64)
-    );
+    if args.source.as_os_str() == "-" || args.source.is_file() {
+        return print_single_digest(&args);
+    }
 
-    println!("Appuyez sur Entrée pour quitter...");
-    let mut pause = String::new();
-    io::stdin().read_line(&mut pause).unwrap();
+    if args.print0 {
+        check_print0(&args)?;
+    }
+    if args.template.is_some() && !matches!(args.format, OutputFormat::Text) {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("--template {}", Msg::NeedsTextFormat.text(args.lang)),
+        ));
+    }
+    if args.manifest_checksum && !matches!(args.format, OutputFormat::Text | OutputFormat::Sfv) {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!(
+                "--manifest-checksum {}",
+                Msg::NeedsTextOrSfv.text(args.lang)
+            ),
+        ));
+    }
+    if matches!(args.format, OutputFormat::Sfv) && args.algo != [HashAlgo::Crc32] {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("--format sfv {}", Msg::NeedsCrc32Alone.text(args.lang)),
+        ));
+    }
 
-    Ok(())
-}
+    // Destination du manifeste (None pour stdout)
+    let output_file = match &args.output {
+        Some(path) if path.as_os_str() == "-" => None,
+        Some(path) => Some(path.clone()),
+        // --per-dir : celui de la racine, qui porte le verrou
+        None if args.per_dir => Some(args.source.join(&args.name)),
+        None => Some(args.output_dir.join(&args.name)),
+    };
+    let state_file = match (&output_file, args.update) {
+        (Some(path), true) => Some(state_path(path)),
+        (None, true) => {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("--update {}", Msg::NeedsManifestFile.text(args.lang)),
+            ));
+        }
+        (_, false) => None,
+    };
+    // Un seul zhashgen à la fois par manifeste, jusqu'à la fin du programme. Pris avant de
+    // lire l'état ou le manifeste (--append) : un lancement concurrent qui se termine
+    // pendant le parcours ferait sinon écrire une seconde fois ses propres entrées.
+    let lock_file = output_file.as_deref().map(lock_path);
+    let lock = match &lock_file {
+        Some(path) if !args.dry_run => {
+            if let Some(parent) = path.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            Some(OutputLock::acquire(path, &args)?)
+        }
+        _ => None,
+    };
+    let previous = match &state_file {
+        Some(path) => load_state(path, &args.algo, &args.hash_config(), args.lang)?,
+        None => HashMap::new(),
+    };
 
-// Fonction pour hacher un fichier
-fn hash_file(path: &Path, full_load_limit: u64, algo: HashAlgo) -> io::Result<(String, u64)> {
-    let meta = fs::metadata(path)?;
-    let size = meta.len();
-    let mut file = File::open(path)?;
-
-    // --------------------------------------------------------------------------------
-    // CAS 1: PETIT FICHIER (Charge complète en mémoire pour une performance maximale)
-    // --------------------------------------------------------------------------------
-    if size <= full_load_limit {
-        let mut buf = Vec::with_capacity(size as usize);
-        file.read_to_end(&mut buf)?;
-
-        let digest = match algo {
-            HashAlgo::Crc32 => format!("{:08x}", crc32fast::hash(&buf)),
-            HashAlgo::Crc32c => format!("{:08x}", crc32c(&buf)),
-            HashAlgo::City128 => format!("{:032x}", city_hash_128(&buf)),
-            HashAlgo::Xxh3 => format!("{:016x}", xxhash_rust::xxh3::xxh3_64(&buf)),
-            HashAlgo::Sha256 => format!("{:x}", Sha256::digest(&buf)),
-            HashAlgo::Sha512 => format!("{:x}", Sha512::digest(&buf)),
-            HashAlgo::Blake2b => format!("{:x}", Blake2b512::digest(&buf)),
-            HashAlgo::Blake2s => format!("{:x}", Blake2s256::digest(&buf)),
-        };
-        Ok((digest, size))
-    }
-    // --------------------------------------------------------------------------------
-    // CAS 2: GRAND FICHIER (Mode streaming pour économiser la RAM)
-    // --------------------------------------------------------------------------------
-    else {
-        // CityHash ne supporte pas le mode streaming avec le crate actuel,
-        // nous devons donc charger le fichier en entier, ce qui est contraire
-        // à l'objectif de faible RAM. On retourne une erreur pour forcer l'utilisateur
-        // à augmenter la limite ou choisir un autre algo.
-        if let HashAlgo::City128 = algo {
+    let errors_file = errors_path(&args, output_file.as_deref());
+    let excluded: Vec<PathBuf> = output_file
+        .iter()
+        .chain(&state_file)
+        .chain(&lock_file)
+        .chain(&errors_file)
+        .cloned()
+        .collect();
+    // Avec --output, le manifeste peut être à la racine de --source : seul le fichier
+    // est exclu, pas son répertoire
+    let output_dir = (args.output.is_none() && !args.per_dir && !args.no_output_exclude)
+        .then_some(args.output_dir.as_path());
+    let (mut files, skipped_empty) = collect_files(&args, &excluded, output_dir)?;
+    // --append : contenu actuel du manifeste, complété en fin de fichier
+    let mut appended = match (&output_file, args.append) {
+        (Some(path), true) => Some(read_appended(path, &args)?),
+        (None, true) => {
             return Err(io::Error::new(
                 io::ErrorKind::InvalidInput,
-                "City128 ne supporte pas le streaming. Veuillez augmenter --full-load-limit ou choisir un autre algo.",
+                format!("--append {}", Msg::NeedsManifestFile.text(args.lang)),
             ));
         }
+        (_, false) => None,
+    };
+    if let Some(appended) = &mut appended {
+        // Un chemin déjà enregistré (ou listé deux fois) n'est haché qu'une fois
+        let mut present = std::mem::take(&mut appended.paths);
+        let before = files.len();
+        files.retain(|path| {
+            let rel = path.strip_prefix(&args.source).unwrap_or(path);
+            present.insert(recorded_bytes(path, rel, &args))
+        });
+        info!(
+            "--append : {} {}",
+            before - files.len(),
+            Msg::AlreadyPresent.text(args.lang)
+        );
+    }
+    if args.per_dir {
+        // Manifestes des sous-répertoires laissés par un passage précédent
+        let name = std::ffi::OsStr::new(&args.name);
+        files.retain(|path| log_skip(path.file_name() != Some(name), path, "--per-dir"));
+    }
+
+    if args.dry_run {
+        return list_files(&files, &args);
+    }
+    if args.align {
+        PATH_WIDTH.get_or_init(|| path_width(&files, &args));
+    }
+
+    // Premier Ctrl-C : arrêt propre après les fichiers en cours ; second Ctrl-C : arrêt immédiat
+    let lang = args.lang;
+    ctrlc::set_handler(move || {
+        if INTERRUPTED.swap(true, Ordering::SeqCst) {
+            std::process::exit(EXIT_INTERRUPTED);
+        }
+        eprintln!("\n{}", Msg::Interrupting.text(lang));
+    })
+    .map_err(io::Error::other)?;
 
-        let mut hasher: Box<dyn HashingStream> = match algo {
-            HashAlgo::Crc32 => Box::new(Crc32Stream::new()),
-            HashAlgo::Crc32c => Box::new(Crc32cStream::new()),
-            HashAlgo::Xxh3 => Box::new(Xxh3Stream::new()),
-            HashAlgo::Sha256 => Box::new(CryptoStream::<Sha256>::new()),
-            HashAlgo::Sha512 => Box::new(CryptoStream::<Sha512>::new()),
-            HashAlgo::Blake2b => Box::new(CryptoStream::<Blake2b512>::new()),
-            HashAlgo::Blake2s => Box::new(CryptoStream::<Blake2s256>::new()),
-            // City128 est géré ci-dessus
-            _ => unreachable!(),
+    if args.watch {
+        let Some(manifest) = &output_file else {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("--watch {}", Msg::NeedsManifestFile.text(args.lang)),
+            ));
+        };
+        let watched = Watched {
+            manifest,
+            state_file: state_file.as_deref(),
+            excluded: &excluded,
+            output_dir,
         };
+        return run_watch(&args, &watched, files, &previous);
+    }
+
+    let start = Instant::now();
 
-        let mut buffer = [0u8; BUFFER_SIZE];
-        loop {
-            let n = file.read(&mut buffer)?;
-            if n == 0 {
-                break;
+    // La barre de progression est sur stderr : stdout reste propre pour les pipes
+    let mut writer = if args.per_dir {
+        ManifestOutput::PerDir(PerDirWriter {
+            root: &args.source,
+            current: None,
+            count: 0,
+        })
+    } else {
+        match (&output_file, &appended) {
+            (Some(path), Some(appended)) if !appended.empty => {
+                let mut out = BufWriter::new(fs::OpenOptions::new().append(true).open(path)?);
+                // Dernière ligne sans fin de ligne : la suite ne doit pas s'y coller
+                if appended.unterminated {
+                    out.write_all(b"\n")?;
+                }
+                ManifestOutput::Single(ManifestWriter::appending(Box::new(out), &args))
+            }
+            _ => {
+                let out: Box<dyn Write> = match &output_file {
+                    Some(path) => Box::new(BufWriter::new(File::create(path)?)),
+                    None => Box::new(io::stdout().lock()),
+                };
+                ManifestOutput::Single(ManifestWriter::new(out, &args)?)
+            }
+        }
+    };
+    // Le manifeste est écrit au fil de l'eau ; les résultats ne sont conservés en mémoire
+    // que pour le fichier d'état, la recherche de doublons et --sort / --reverse
+    let sorted = args.sort != SortKey::Path || args.reverse;
+    let keep_results = args.update || args.find_dupes || args.dupes_report.is_some() || sorted;
+    let keep_results = keep_results || args.hardlink_dupes;
+    let mut results = Vec::new();
+    let (mut total_bytes, mut total_errors, mut done) = (0u64, 0u64, 0usize);
+    let mut empty_files = skipped_empty;
+    let mut collisions = CollisionCheck::default();
+    let mut errors = ErrorLog::new(errors_file, &args)?;
+    hash_files(&files, &args.source, &args, &previous, |mut record| {
+        if !sorted {
+            writer.write(&mut record, &mut errors, &args)?;
+        }
+        done += 1;
+        match &record.result {
+            Ok(digest) => {
+                total_bytes += digest.size;
+                empty_files += usize::from(digest.size == 0);
+                // --head : seuls les premiers octets sont hachés, tailles comparées d'autant
+                let hashed = args.head.map_or(digest.size, |head| digest.size.min(head));
+                collisions.add(format_digest(digest, &args), hashed, &record.rel);
             }
-            hasher.update(&buffer[..n]);
+            Err(_) => total_errors += 1,
+        }
+        if keep_results {
+            results.push(record);
         }
+        Ok(())
+    })?;
+    if sorted {
+        sort_records(&mut results, &args);
+        for record in &mut results {
+            writer.write(record, &mut errors, &args)?;
+        }
+    }
+    let manifests = writer.finish(&args)?;
+    let errors_file = errors.finish()?;
+    if let Some(path) = &state_file {
+        save_state(path, &args.algo, &args.hash_config(), &results)?;
+    }
+    collisions.warn(done, args.lang);
 
-        let digest = hasher.finalize();
-        Ok((digest, size))
+    if INTERRUPTED.load(Ordering::SeqCst) {
+        eprintln!(
+            "{} {done}/{} {}",
+            Msg::Interrupted.text(args.lang),
+            files.len(),
+            Msg::Files.text(args.lang)
+        );
+        if let Some(path) = &output_file {
+            eprintln!("{}: {}", Msg::Manifest.text(args.lang), path.display());
+        }
+        // exit ne lance pas les destructeurs
+        drop(lock);
+        std::process::exit(EXIT_INTERRUPTED);
     }
-}
 
-// --------------------------------------------------------------------------------
-// TRAITS ET STRUCTURES POUR LE STREAMING (lecture par blocs)
-// --------------------------------------------------------------------------------
+    let duplicates = if args.find_dupes || args.dupes_report.is_some() || args.hardlink_dupes {
+        let groups = find_duplicates(&results);
+        match &args.dupes_report {
+            Some(path) => {
+                let mut report = BufWriter::new(File::create(path)?);
+                write_duplicates(&mut report, &groups, &args)?;
+                report.flush()?;
+            }
+            None if args.find_dupes => write_duplicates(&mut io::stdout().lock(), &groups, &args)?,
+            None => {}
+        }
+        if args.hardlink_dupes {
+            hardlink_duplicates(&groups, &args)?;
+        }
+        Some(groups)
+    } else {
+        None
+    };
 
-trait HashingStream {
-    fn update(&mut self, data: &[u8]);
-    fn finalize(&mut self) -> String;
-}
+    // En sortie stdout, ni statistiques ni pause pour ne pas polluer le pipe
+    let Some(output_file) = output_file else {
+        return exit_status(total_errors, lock);
+    };
 
-// Implémentation générique pour les hashes cryptographiques (SHA, Blake)
-struct CryptoStream<T: Digest + Send + 'static> {
-    hasher: T,
-}
+    if !args.quiet {
+        let lang = args.lang;
+        let elapsed = start.elapsed().as_secs_f64();
+        let stat = |msg: Msg, value: String| println!("{:<21}: {}", msg.text(lang), value);
+        if args.per_dir {
+            println!(
+                "\n{} {} ({manifests} {})",
+                Msg::Done.text(lang),
+                args.source.display(),
+                Msg::Manifests.text(lang)
+            );
+        } else {
+            println!("\n{} {}", Msg::Done.text(lang), output_file.display());
+        }
+        println!("{}", Msg::Statistics.text(lang));
+        stat(Msg::FilesProcessed, files.len().to_string());
+        match &errors_file {
+            Some(path) => stat(Msg::Errors, format!("{total_errors} ({})", path.display())),
+            None => stat(Msg::Errors, total_errors.to_string()),
+        }
+        if args.skip_empty {
+            stat(
+                Msg::EmptyFiles,
+                format!("{empty_files} ({})", Msg::Skipped.text(lang)),
+            );
+        } else {
+            stat(Msg::EmptyFiles, empty_files.to_string());
+        }
+        stat(Msg::TotalSize, human_readable(total_bytes));
+        stat(Msg::Elapsed, format!("{:.2} s", elapsed));
+        stat(
+            Msg::Throughput,
+            format!(
+                "{}/s",
+                human_readable((total_bytes as f64 / elapsed) as u64)
+            ),
+        );
+        if let Some(groups) = &duplicates {
+            stat(
+                Msg::Duplicates,
+                format!(
+                    "{} {}, {} {}",
+                    groups.len(),
+                    Msg::Groups.text(lang),
+                    human_readable(reclaimable_bytes(groups)),
+                    Msg::Reclaimable.text(lang)
+                ),
+            );
+        }
+    }
 
-impl<T: Digest + Send + 'static> CryptoStream<T> {
-    fn new() -> Self {
-        CryptoStream { hasher: T::new() }
+    if !args.no_pause && io::stdin().is_terminal() {
+        println!("{}", Msg::PressEnter.text(args.lang));
+        let mut pause = String::new();
+        io::stdin().read_line(&mut pause).unwrap();
     }
+
+    exit_status(total_errors, lock)
 }
 
-impl<T: Digest + Send + 'static> HashingStream for CryptoStream<T> {
-    fn update(&mut self, data: &[u8]) {
-        self.hasher.update(data);
+// Fin de génération : EXIT_FILE_ERRORS si des fichiers n'ont pas pu être hachés. exit ne
+// lance pas les destructeurs : le verrou est libéré avant.
+fn exit_status(total_errors: u64, lock: Option<OutputLock>) -> io::Result<()> {
+    if total_errors > 0 {
+        drop(lock);
+        std::process::exit(EXIT_FILE_ERRORS);
     }
-    fn finalize(&mut self) -> String {
-        // Swap out the current hasher with a fresh one and finalize the old
-        // instance. This avoids requiring the `FixedOutputReset` trait.
-        let hasher = std::mem::replace(&mut self.hasher, T::new());
-        let result = hasher.finalize();
-        result.iter().map(|b| format!("{:02x}", b)).collect()
+    Ok(())
+}
+
+// Hache les fichiers en parallèle et transmet chaque résultat à `sink` dès que possible,
+// dans un ordre indépendant de l'ordonnancement rayon et de la plateforme (tri sur le
+// chemin relatif normalisé). Les résultats arrivés en avance attendent dans un
+// ReorderBuffer que les précédents soient terminés.
+fn hash_files(
+    files: &[PathBuf],
+    root: &Path,
+    args: &Args,
+    previous: &HashMap<String, StateEntry>,
+    mut sink: impl FnMut(FileRecord) -> io::Result<()>,
+) -> io::Result<()> {
+    // Tailles connues d'avance : la progression (et l'ETA) suit les octets, pas les fichiers.
+    // Un fichier illisible compte pour 0 : l'erreur apparaîtra au hachage.
+    let mut files: Vec<(PathBuf, &PathBuf, u64)> = files
+        .par_iter()
+        .map(|path| {
+            let rel = path.strip_prefix(root).unwrap_or(path).to_path_buf();
+            let size = fs::metadata(long_path(path)).map_or(0, |meta| meta.len());
+            (rel, path, size)
+        })
+        .collect();
+    if args.per_dir {
+        // Les fichiers d'un même répertoire se suivent : un seul manifeste ouvert à la fois
+        files.sort_by_cached_key(|(rel, _, _)| (rel.parent().map(slash_path), slash_path(rel)));
+    } else {
+        files.sort_by_cached_key(|(rel, _, _)| slash_path(rel));
     }
+
+    let total_bytes = files.iter().map(|(_, _, size)| size).sum();
+    let progress = Progress::new(total_bytes, files.len(), ProgressUnit::Bytes, args);
+
+    let config = args.hash_config();
+    let pipeline = Pipeline::from_args(args)?;
+    let (sender, receiver) = mpsc::channel();
+    let result = thread::scope(|scope| {
+        let (files, progress, config) = (&files, &progress, &config);
+        let hash_pool = pipeline.as_ref().map(|pipeline| &pipeline.hash);
+        let run = move || {
+            // Un envoi échoue quand le récepteur a abandonné (erreur) : on s'arrête
+            files.par_iter().enumerate().try_for_each_with(
+                sender,
+                |sender, (index, (rel, path, size))| {
+                    if INTERRUPTED.load(Ordering::Relaxed) {
+                        return Err(());
+                    }
+                    let record = hash_record(path, rel, args, previous, config, hash_pool);
+                    progress.file_done(&slash_path(rel), *size);
+                    sender.send((index, record)).map_err(drop)
+                },
+            )
+        };
+        scope.spawn(|| match &pipeline {
+            Some(pipeline) => pipeline.read.install(run),
+            None => run(),
+        });
+
+        let mut reorder = ReorderBuffer::new();
+        for (index, record) in receiver {
+            reorder.push(index, record, |record| sink(record?))?;
+        }
+        // Après une interruption, il peut rester des trous : ce qui a été calculé est
+        // tout de même transmis, dans l'ordre
+        reorder.flush(|record| sink(record?))
+    });
+    progress.finish();
+    result
 }
 
-// Implémentation pour CRC32
-struct Crc32Stream {
-    hasher: Crc32Hasher,
+// Remise en ordre de résultats numérotés arrivant dans le désordre : chaque élément est
+// émis dès que tous ceux d'indice inférieur l'ont été. Seuls les éléments en avance sur
+// le plus ancien encore en cours restent en mémoire, jamais le manifeste entier.
+struct ReorderBuffer<T> {
+    next: usize,
+    pending: BTreeMap<usize, T>,
 }
 
-impl Crc32Stream {
+impl<T> ReorderBuffer<T> {
     fn new() -> Self {
-        Crc32Stream {
-            hasher: Crc32Hasher::new(),
+        ReorderBuffer {
+            next: 0,
+            pending: BTreeMap::new(),
         }
     }
-}
 
-impl HashingStream for Crc32Stream {
-    fn update(&mut self, data: &[u8]) {
-        self.hasher.update(data);
+    // Range `item` puis émet le préfixe contigu devenu disponible
+    fn push(
+        &mut self,
+        index: usize,
+        item: T,
+        mut emit: impl FnMut(T) -> io::Result<()>,
+    ) -> io::Result<()> {
+        self.pending.insert(index, item);
+        while let Some(item) = self.pending.remove(&self.next) {
+            emit(item)?;
+            self.next += 1;
+        }
+        Ok(())
     }
-    fn finalize(&mut self) -> String {
-        format!("{:08x}", self.hasher.clone().finalize())
+
+    // Émet le reste dans l'ordre des indices, trous compris
+    fn flush(self, mut emit: impl FnMut(T) -> io::Result<()>) -> io::Result<()> {
+        self.pending.into_values().try_for_each(&mut emit)
     }
 }
 
-// Implémentation pour CRC32C (streaming via table-driven algorithm)
-struct Crc32cStream {
-    digest: u32,
+// Pools distincts pour la lecture et le hachage (--read-threads / --hash-threads)
+struct Pipeline {
+    read: rayon::ThreadPool,
+    hash: rayon::ThreadPool,
 }
 
-impl Crc32cStream {
-    fn new() -> Self {
-        // Start with all-ones as is standard for CRC-32C (we XOR at the end)
-        Crc32cStream { digest: 0xFFFF_FFFFu32 }
+impl Pipeline {
+    // None sans aucune des deux options : le pool global lit et hache, comme auparavant
+    fn from_args(args: &Args) -> io::Result<Option<Self>> {
+        if args.read_threads.is_none() && args.hash_threads.is_none() {
+            return Ok(None);
+        }
+        let pool = |threads: Option<usize>| {
+            rayon::ThreadPoolBuilder::new()
+                .num_threads(threads.unwrap_or(args.threads))
+                .build()
+                .map_err(io::Error::other)
+        };
+        Ok(Some(Pipeline {
+            read: pool(args.read_threads)?,
+            hash: pool(args.hash_threads)?,
+        }))
     }
 }
 
-impl HashingStream for Crc32cStream {
-    fn update(&mut self, data: &[u8]) {
-        let table = &*CRC32C_TABLE;
-        let mut crc = self.digest;
-        for &b in data {
-            crc = (crc >> 8) ^ table[((crc as u8) ^ b) as usize];
+// Hachage d'un fichier ; avec --on-error fail, l'erreur interrompt la génération
+fn hash_record(
+    path: &Path,
+    rel: &Path,
+    args: &Args,
+    previous: &HashMap<String, StateEntry>,
+    config: &HashConfig,
+    hash_pool: Option<&rayon::ThreadPool>,
+) -> io::Result<FileRecord> {
+    let file_start = Instant::now();
+    let link = symlink_entry(path, args);
+    // Un lien se rehache toujours : sa date (suivie par --update) est celle de la cible
+    let reused = link
+        .is_none()
+        .then(|| unchanged_digests(previous, rel, path))
+        .flatten();
+    let digests = match reused {
+        _ if link.is_some() => {
+            info!(
+                "{} -> {}",
+                path.display(),
+                Msg::SymbolicLink.text(args.lang)
+            );
+            guard_panic(path, args, || hash_symlink_target(path, &args.algo, config))
+        }
+        Some(digests) => {
+            debug!(
+                "{} {}",
+                Msg::UnchangedReused.text(args.lang),
+                path.display()
+            );
+            Ok(digests)
         }
-        self.digest = crc;
+        None => {
+            info!("{}", path.display());
+            guard_panic(path, args, || match hash_pool {
+                Some(pool) => hash_file_pipelined(path, &args.algo, config, pool),
+                None => hash_file_multi(path, &args.algo, config),
+            })
+            .map_err(|e| describe_symlink_error(path, e, args.lang))
+        }
+    };
+    let elapsed = file_start.elapsed();
+    match &digests {
+        Ok(_) => trace!("{} : {:.3} s", path.display(), elapsed.as_secs_f64()),
+        Err(e) => error!("{}: {}", path.display(), e),
     }
-    fn finalize(&mut self) -> String {
-        let final_crc = self.digest ^ 0xFFFF_FFFFu32;
-        format!("{:08x}", final_crc)
+    if let Some(digest) = digests
+        .as_ref()
+        .ok()
+        .and_then(|d| d.first())
+        .filter(|d| d.changed)
+    {
+        eprintln!(
+            "{} {} {} ({} {})",
+            Msg::Warning.text(args.lang),
+            path.display(),
+            Msg::ChangedWhileRead.text(args.lang),
+            digest.size,
+            Msg::BytesHashed.text(args.lang)
+        );
     }
+    // Le premier digest est le principal, les autres accompagnent --algo a,b
+    let (result, extra) = match digests {
+        Ok(mut digests) => {
+            let primary = digests.remove(0);
+            (Ok(primary), digests)
+        }
+        Err(e) if args.on_error == OnError::Fail => {
+            return Err(io::Error::new(
+                e.kind(),
+                format!("{}: {} ({})", path.display(), e, ErrorClass::of(&e).label()),
+            ));
+        }
+        Err(e) => (Err(e), Vec::new()),
+    };
+    Ok(FileRecord {
+        path: path.to_path_buf(),
+        rel: rel.to_path_buf(),
+        result,
+        extra,
+        elapsed,
+        link,
+    })
 }
 
-// Implémentation pour XXH3
-struct Xxh3Stream {
-    hasher: Xxh3,
+// --continue-on-panic : la panique de `hash` devient une erreur de `path`. Le message
+// standard de la panique est déjà sur stderr ; le chemin en cause est ajouté.
+fn guard_panic<T>(path: &Path, args: &Args, hash: impl FnOnce() -> io::Result<T>) -> io::Result<T> {
+    if !args.continue_on_panic {
+        return hash();
+    }
+    std::panic::catch_unwind(std::panic::AssertUnwindSafe(hash)).unwrap_or_else(|payload| {
+        let message = payload
+            .downcast_ref::<&str>()
+            .copied()
+            .or_else(|| payload.downcast_ref::<String>().map(String::as_str))
+            .unwrap_or("panique sans message");
+        eprintln!(
+            "{} {} {} ({message}), {}",
+            Msg::Warning.text(args.lang),
+            Msg::PanicWhileHashing.text(args.lang),
+            path.display(),
+            Msg::CountedAsError.text(args.lang)
+        );
+        Err(io::Error::other(format!(
+            "{} {message}",
+            Msg::Panic.text(args.lang)
+        )))
+    })
 }
 
-impl Xxh3Stream {
-    fn new() -> Self {
-        Xxh3Stream {
-            hasher: Xxh3::new(),
-        }
+// Cible du lien symbolique `path` s'il est enregistré comme tel (--include-symlinks-as-entries)
+fn symlink_entry(path: &Path, args: &Args) -> Option<PathBuf> {
+    if !args.include_symlinks_as_entries || !path.is_symlink() {
+        return None;
     }
+    fs::read_link(long_path(path)).ok()
 }
 
-impl HashingStream for Xxh3Stream {
-    fn update(&mut self, data: &[u8]) {
-        self.hasher.update(data);
+// Digest d'un lien : celui du chemin cible tel que stocké, sans suivre le lien.
+// La taille est celle de la cible textuelle, la date celle du lien lui-même.
+fn hash_symlink_target(
+    path: &Path,
+    algos: &[HashAlgo],
+    config: &HashConfig,
+) -> io::Result<Vec<FileDigest>> {
+    let path = long_path(path);
+    let target = fs::read_link(&path)?;
+    let modified = fs::symlink_metadata(&path)?.modified().ok();
+    let bytes = target.as_os_str().as_encoded_bytes();
+    algos
+        .iter()
+        .map(|&algo| {
+            let config = HashConfig { algo, ..*config };
+            let digest = hash_reader(bytes, &config)?;
+            Ok(FileDigest { modified, ..digest })
+        })
+        .collect()
+}
+
+// Fichiers à hacher : --files-from ou parcours de --source, puis bornes de taille.
+// Retourne aussi le nombre de fichiers vides écartés par --skip-empty.
+fn collect_files(
+    args: &Args,
+    excluded: &[PathBuf],
+    output_dir: Option<&Path>,
+) -> io::Result<(Vec<PathBuf>, usize)> {
+    let mut files = match &args.files_from {
+        Some(list) => read_file_list(list)?,
+        None => scan_source(args, &args.source, excluded, output_dir)?,
+    };
+    let skipped_empty = retain_size_range(&mut files, args);
+    Ok((files, skipped_empty))
+}
+
+// --min-size / --max-size / --skip-empty ; un fichier illisible est conservé : l'erreur
+// apparaîtra au hachage. Retourne le nombre de fichiers vides écartés par --skip-empty.
+fn retain_size_range(files: &mut Vec<PathBuf>, args: &Args) -> usize {
+    if args.min_size.is_none() && args.max_size.is_none() && !args.skip_empty {
+        return 0;
+    }
+    let mut skipped_empty = 0;
+    files.retain(|path| {
+        let Ok(meta) = fs::metadata(long_path(path)) else {
+            return true;
+        };
+        if args.skip_empty && meta.len() == 0 {
+            skipped_empty += 1;
+            return log_skip(false, path, "--skip-empty");
+        }
+        let keep = args.min_size.is_none_or(|min| meta.len() >= min)
+            && args.max_size.is_none_or(|max| meta.len() <= max);
+        log_skip(keep, path, "--min-size/--max-size")
+    });
+    skipped_empty
+}
+
+// --dry-run : chemins tels qu'ils figureraient dans le manifeste, dans le même ordre
+fn list_files(files: &[PathBuf], args: &Args) -> io::Result<()> {
+    let mut rels: Vec<_> = files
+        .iter()
+        .map(|path| recorded_bytes(path, path.strip_prefix(&args.source).unwrap_or(path), args))
+        .collect();
+    rels.sort();
+    // Un fichier illisible compte pour 0 : seules les métadonnées sont consultées
+    let total_bytes: u64 = files
+        .par_iter()
+        .map(|path| fs::metadata(long_path(path)).map_or(0, |meta| meta.len()))
+        .sum();
+
+    let mut out: Box<dyn Write> = match &args.output {
+        Some(path) if path.as_os_str() != "-" => {
+            if let Some(parent) = path.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            Box::new(BufWriter::new(File::create(path)?))
+        }
+        _ => Box::new(io::stdout().lock()),
+    };
+    for rel in &rels {
+        out.write_all(rel)?;
+        out.write_all(line_end(args))?;
     }
-    fn finalize(&mut self) -> String {
-        format!("{:016x}", self.hasher.digest())
+    out.flush()?;
+    eprintln!(
+        "{} {}, {}",
+        rels.len(),
+        Msg::Files.text(args.lang),
+        human_readable(total_bytes)
+    );
+    Ok(())
+}
+
+// --print0 ne vaut que pour des enregistrements « chemin » ou « digest chemin » sur stdout
+fn check_print0(args: &Args) -> io::Result<()> {
+    let to_stdout = match &args.output {
+        Some(path) => path.as_os_str() == "-",
+        // --dry-run écrit sur stdout par défaut, la génération dans --output-dir
+        None => args.dry_run,
+    };
+    let message = if !to_stdout {
+        Msg::NeedsStdout
+    } else if args.dry_run {
+        return Ok(());
+    } else if !matches!(args.format, OutputFormat::Text) {
+        Msg::NeedsTextFormat
+    } else if args.with_meta || !args.extra_algos().is_empty() {
+        Msg::SingleColumn
+    } else {
+        return Ok(());
+    };
+    Err(io::Error::new(
+        io::ErrorKind::InvalidInput,
+        format!("--print0 {}", message.text(args.lang)),
+    ))
+}
+
+// Fin d'enregistrement des manifestes : NUL avec --print0, sinon selon --line-ending
+fn line_end(args: &Args) -> &'static [u8] {
+    match args.line_ending {
+        _ if args.print0 => b"\0",
+        LineEnding::Crlf => b"\r\n",
+        LineEnding::Native if cfg!(windows) => b"\r\n",
+        LineEnding::Lf | LineEnding::Native => b"\n",
     }
 }
 
+// Un seul fichier (ou stdin en streaming) : digest seul sur stdout, sans manifeste
+fn print_single_digest(args: &Args) -> io::Result<()> {
+    let digest = if args.source.as_os_str() == "-" {
+        hash_reader(io::stdin().lock(), &args.hash_config())
+    } else {
+        hash_file(&args.source, &args.hash_config())
+    };
+    // Fichier illisible : erreur de fichier comme en génération, pas erreur fatale
+    let digest = digest.unwrap_or_else(|e| {
+        eprintln!("[ERROR] {}: {e}", args.source.display());
+        std::process::exit(EXIT_FILE_ERRORS);
+    });
+    println!("{}", format_digest(&digest, args));
+    Ok(())
+}
+
 // --------------------------------------------------------------------------------
-// UTILS
+// COLLECTE DES FICHIERS
 // --------------------------------------------------------------------------------
 
-fn get_interactive_args() -> io::Result<Args> {
-    println!("=== NewCrc gen v2 ===");
-    println!("Choix de l'algorithme :");
-    println!("  1. CRC32");
-    println!("  2. CRC32C (Castagnoli)");
-    println!("  3. City128 (Attention: Ne supporte pas le streaming pour gros fichiers)");
-    println!("  4. XXH3 (défaut)");
-    println!("  5. SHA256");
-    println!("  6. SHA512");
-    println!("  7. Blake2b (512-bit)");
-    println!("  8. Blake2s (256-bit)");
-    print!("Votre choix [1-8] : ");
-    io::stdout().flush()?;
+// Parcourt `root` (--source ou l'arbre de --compare) hors fichiers produits et hors
+// `output_dir` (sous-arbre entier, jamais parcouru), filtres appliqués
+fn scan_source(
+    args: &Args,
+    root: &Path,
+    excluded: &[PathBuf],
+    output_dir: Option<&Path>,
+) -> io::Result<Vec<PathBuf>> {
+    let globs = GlobFilter::new(&args.include, &args.exclude, args.lang)?;
+    // Un fichier produit qui n'existe pas encore ne peut pas figurer dans le parcours
+    let excluded: Vec<FileId> = excluded.iter().filter_map(|path| file_id(path)).collect();
+    let output_dir = output_dir.and_then(file_id);
+    let lang = args.lang;
+    // Un seul stat par répertoire ; la racine n'est jamais écartée (--output-dir = --source)
+    let in_output_dir = move |path: &Path, depth: usize, is_dir: bool| {
+        let skip = is_dir && depth > 0 && output_dir.is_some() && file_id(path) == output_dir;
+        if skip {
+            debug!(
+                "{} {} (--output-dir)",
+                Msg::SkippedFile.text(lang),
+                path.display()
+            );
+        }
+        skip
+    };
+    // Parcours sous le préfixe long de Windows (chemins profonds, partages UNC) ; les
+    // chemins trouvés sont ramenés sous `root` puis filtrés et enregistrés sans préfixe
+    let walk_root = long_path(root).into_owned();
+    // Un répertoire exclu par .newcrcignore n'est pas parcouru
+    let ignore_file = load_ignore_file(args, root, &walk_root)?;
+    let ignored = move |path: &Path, depth: usize, is_dir: bool| {
+        let skip = depth > 0
+            && ignore_file
+                .as_ref()
+                .is_some_and(|ignore| ignore.matched(path, is_dir).is_ignore());
+        if skip {
+            debug!(
+                "{} {} (.newcrcignore)",
+                Msg::SkippedFile.text(lang),
+                path.display()
+            );
+        }
+        skip
+    };
+    let under_root = |path: PathBuf| match path.strip_prefix(&walk_root) {
+        Ok(rel) if rel.as_os_str().is_empty() => root.to_path_buf(),
+        Ok(rel) => root.join(rel),
+        Err(_) => path,
+    };
 
-    let mut choice_input = String::new();
-    io::stdin().read_line(&mut choice_input)?;
-
-    let (algo, filename) = match choice_input.trim() {
-        "1" => (HashAlgo::Crc32, "CRC.crc32"),
-        "2" => (HashAlgo::Crc32c, "CRC.crc32c"),
-        "3" => (HashAlgo::City128, "CRC.city128"),
-        "5" => (HashAlgo::Sha256, "CRC.sha256"),
-        "6" => (HashAlgo::Sha512, "CRC.sha512"),
-        "7" => (HashAlgo::Blake2b, "CRC.blake2b"),
-        "8" => (HashAlgo::Blake2s, "CRC.blake2s"),
-        _ => (HashAlgo::Xxh3, "CRC.xxhash3"), // Défaut Xxh3
-    };
-
-    Ok(Args {
-        source: PathBuf::from("."),
-        output_dir: PathBuf::from("./NewCrc"),
-        name: filename.to_string(),
-        full_load_limit: DEFAULT_FULL_LOAD_LIMIT,
-        threads: num_cpus::get(),
-        algo,
-    })
-}
+    let entries: Box<dyn Iterator<Item = PathBuf>> = if args.respect_gitignore {
+        // Les fichiers cachés restent inclus comme avec WalkDir (sauf --skip-hidden) ;
+        // seules les règles d'exclusion git/ignore s'appliquent, même hors dépôt git
+        let min_depth = args.min_depth.unwrap_or(0);
+        let links = args.include_symlinks_as_entries;
+        Box::new(
+            WalkBuilder::new(&walk_root)
+                .hidden(args.skip_hidden)
+                .require_git(false)
+                .max_depth(args.max_depth)
+                .follow_links(args.follow_symlinks)
+                .filter_entry(move |e| {
+                    let is_dir = e.file_type().is_some_and(|t| t.is_dir());
+                    !in_output_dir(e.path(), e.depth(), is_dir)
+                        && !ignored(e.path(), e.depth(), is_dir)
+                })
+                .build()
+                .filter_map(|e| e.ok())
+                .filter(move |e| e.depth() >= min_depth)
+                .filter(move |e| {
+                    e.file_type()
+                        .is_some_and(|t| t.is_file() || (links && t.is_symlink()))
+                })
+                .map(|e| e.into_path()),
+        )
+    } else {
+        let mut walker = WalkDir::new(&walk_root);
+        if let Some(depth) = args.max_depth {
+            walker = walker.max_depth(depth);
+        }
+        if let Some(depth) = args.min_depth {
+            walker = walker.min_depth(depth);
+        }
+        let follow = args.follow_symlinks;
+        let links = args.include_symlinks_as_entries;
+        let skip_hidden = args.skip_hidden;
+        // Plusieurs liens peuvent mener au même répertoire : on ne le parcourt qu'une fois
+        let mut visited = HashSet::new();
+        Box::new(
+            walker
+                .follow_links(follow)
+                .into_iter()
+                .filter_entry(move |e| {
+                    // La racine n'est jamais considérée comme cachée (ex. --source .)
+                    if skip_hidden && e.depth() > 0 && is_hidden(e.file_name()) {
+                        debug!(
+                            "{} {} (--skip-hidden)",
+                            Msg::SkippedFile.text(args.lang),
+                            e.path().display()
+                        );
+                        return false;
+                    }
+                    let is_dir = e.file_type().is_dir();
+                    if in_output_dir(e.path(), e.depth(), is_dir)
+                        || ignored(e.path(), e.depth(), is_dir)
+                    {
+                        return false;
+                    }
+                    !follow
+                        || !e.file_type().is_dir()
+                        || visited.insert(
+                            fs::canonicalize(e.path()).unwrap_or_else(|_| e.path().to_path_buf()),
+                        )
+                })
+                .filter_map(move |res| match res {
+                    Ok(e) => {
+                        let t = e.file_type();
+                        (t.is_file() || (links && t.is_symlink())).then(|| e.into_path())
+                    }
+                    // Lien cassé : on le conserve pour que l'erreur figure dans le manifeste
+                    Err(err) if follow && err.loop_ancestor().is_none() => err
+                        .path()
+                        .filter(|path| path.is_symlink())
+                        .map(Path::to_path_buf),
+                    Err(_) => None,
+                }),
+        )
+    };
 
-fn human_readable(num_bytes: u64) -> String {
-    let units = ["B", "KiB", "MiB", "GiB", "TiB", "PiB"];
+    let selected = entries
+        .map(under_root)
+        .filter(|path| {
+            let keep = globs.accepts(path.strip_prefix(root).unwrap_or(path));
+            log_skip(keep, path, "--include/--exclude")
+        })
+        .filter(|path| log_skip(has_allowed_extension(path, &args.ext), path, "--ext"))
+        // Filtrer les fichiers de sortie eux-mêmes
+        .filter(|path| {
+            let keep =
+                excluded.is_empty() || file_id(path).is_none_or(|id| !excluded.contains(&id));
+            log_skip(keep, path, Msg::OwnOutput.text(args.lang))
+        });
+
+    // --max-files / --max-total-size : vérifiés au fil du parcours, qui s'arrête aussitôt
+    let mut files = Vec::new();
+    let mut total_size = 0u64;
+    for path in selected {
+        if args.max_files.is_some_and(|max| files.len() >= max) {
+            let found = format!(
+                "{} {} {}",
+                Msg::MoreThan.text(args.lang),
+                files.len(),
+                Msg::Files.text(args.lang)
+            );
+            return Err(scan_limit_error(root, found, "--max-files", args.lang));
+        }
+        if let Some(max) = args.max_total_size {
+            total_size += fs::metadata(long_path(&path)).map_or(0, |meta| meta.len());
+            if total_size > max {
+                let found = format!("{} {}", Msg::MoreThan.text(args.lang), human_readable(max));
+                return Err(scan_limit_error(root, found, "--max-total-size", args.lang));
+            }
+        }
+        files.push(path);
+    }
+    Ok(files)
+}
+
+// Fichier d'exclusion : --ignore-file (obligatoire s'il est donné) ou .newcrcignore à la
+// racine. Ses motifs sont relatifs à --source, comme ceux d'un .gitignore à la racine.
+fn load_ignore_file(args: &Args, root: &Path, walk_root: &Path) -> io::Result<Option<Gitignore>> {
+    let path = match &args.ignore_file {
+        Some(path) => path.clone(),
+        None => {
+            let default = root.join(IGNORE_FILE);
+            if !default.is_file() {
+                return Ok(None);
+            }
+            default
+        }
+    };
+    let invalid = |e: ignore::Error| {
+        io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!(
+                "{} {} ({e})",
+                Msg::InvalidIgnoreFile.text(args.lang),
+                path.display()
+            ),
+        )
+    };
+    let mut builder = GitignoreBuilder::new(walk_root);
+    if let Some(e) = builder.add(&path) {
+        return Err(invalid(e));
+    }
+    let ignore = builder.build().map_err(invalid)?;
+    info!(
+        "{} {} {}",
+        ignore.len(),
+        Msg::IgnorePatternsRead.text(args.lang),
+        path.display()
+    );
+    Ok(Some(ignore))
+}
+
+const IGNORE_FILE: &str = ".newcrcignore";
+
+fn scan_limit_error(root: &Path, found: String, option: &str, lang: Lang) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidInput,
+        format!(
+            "{} {found} {} {} ({} {option}), {}",
+            Msg::ScanAborted.text(lang),
+            Msg::Under.text(lang),
+            root.display(),
+            Msg::Limit.text(lang),
+            Msg::NothingHashed.text(lang)
+        ),
+    )
+}
+
+// Identité d'un fichier indépendante du chemin qui y mène (liens, "..", casse...) :
+// (périphérique, inode) sous Unix, un seul stat par fichier ; chemin canonique ailleurs
+#[cfg(unix)]
+type FileId = (u64, u64);
+#[cfg(not(unix))]
+type FileId = PathBuf;
+
+#[cfg(unix)]
+fn file_id(path: &Path) -> Option<FileId> {
+    use std::os::unix::fs::MetadataExt;
+    let meta = fs::metadata(long_path(path)).ok()?;
+    Some((meta.dev(), meta.ino()))
+}
+
+#[cfg(not(unix))]
+fn file_id(path: &Path) -> Option<FileId> {
+    long_path(path).canonicalize().ok()
+}
+
+// Le disque portant `path` est-il rotatif ? Lu dans sysfs (queue/rotational du disque,
+// ou du disque parent pour une partition). None si inconnu (LVM, réseau, autre OS...).
+#[cfg(target_os = "linux")]
+fn is_rotational(path: &Path) -> Option<bool> {
+    use std::os::unix::fs::MetadataExt;
+    let dev = fs::metadata(path).ok()?.dev();
+    // Encodage glibc de dev_t
+    let major = ((dev >> 8) & 0xfff) | ((dev >> 32) & !0xfff);
+    let minor = (dev & 0xff) | ((dev >> 12) & !0xff);
+    let device = Path::new("/sys/dev/block")
+        .join(format!("{major}:{minor}"))
+        .canonicalize()
+        .ok()?;
+    let flag = [device.as_path(), device.parent()?]
+        .iter()
+        .find_map(|dir| fs::read_to_string(dir.join("queue/rotational")).ok())?;
+    Some(flag.trim() == "1")
+}
+
+#[cfg(not(target_os = "linux"))]
+fn is_rotational(_path: &Path) -> Option<bool> {
+    None
+}
+
+// Journalise (-vv) la raison pour laquelle un fichier est écarté
+fn log_skip(keep: bool, path: &Path, reason: &str) -> bool {
+    if !keep {
+        debug!(
+            "{} {} ({reason})",
+            Msg::SkippedFile.text(current_lang()),
+            path.display()
+        );
+    }
+    keep
+}
+
+fn is_hidden(name: &std::ffi::OsStr) -> bool {
+    name.to_str().is_some_and(|name| name.starts_with('.'))
+}
+
+// Précise dans le message d'erreur qu'il s'agit de la cible d'un lien symbolique
+fn describe_symlink_error(path: &Path, err: io::Error, lang: Lang) -> io::Error {
+    match fs::read_link(long_path(path)) {
+        Ok(target) => io::Error::new(
+            err.kind(),
+            format!(
+                "{} {} ({err})",
+                Msg::UnreadableLinkTarget.text(lang),
+                target.display()
+            ),
+        ),
+        Err(_) => err,
+    }
+}
+
+// Liste vide : toutes les extensions sont acceptées
+fn has_allowed_extension(path: &Path, allowed: &[String]) -> bool {
+    allowed.is_empty()
+        || path
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .is_some_and(|ext| {
+                allowed
+                    .iter()
+                    .any(|a| a.trim_start_matches('.').eq_ignore_ascii_case(ext))
+            })
+}
+
+// Filtres --include / --exclude : l'include sert de liste blanche (si non vide),
+// l'exclude en retire ensuite les correspondances
+struct GlobFilter {
+    include: GlobSet,
+    exclude: GlobSet,
+}
+
+impl GlobFilter {
+    fn new(include: &[String], exclude: &[String], lang: Lang) -> io::Result<Self> {
+        Ok(GlobFilter {
+            include: build_globset(include, lang)?,
+            exclude: build_globset(exclude, lang)?,
+        })
+    }
+
+    fn accepts(&self, rel: &Path) -> bool {
+        (self.include.is_empty() || self.include.is_match(rel)) && !self.exclude.is_match(rel)
+    }
+}
+
+fn build_globset(patterns: &[String], lang: Lang) -> io::Result<GlobSet> {
+    let mut builder = GlobSetBuilder::new();
+    for pattern in patterns {
+        let glob = Glob::new(pattern).map_err(|e| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("{} '{pattern}' ({e})", Msg::InvalidGlob.text(lang)),
+            )
+        })?;
+        builder.add(glob);
+    }
+    builder
+        .build()
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e.to_string()))
+}
+
+// Lit une liste de chemins (un par ligne, ou séparés par NUL comme avec `find -print0`).
+// Les chemins absents ne sont pas filtrés : ils produiront une ligne [ERROR] au hachage.
+fn read_file_list(list: &Path) -> io::Result<Vec<PathBuf>> {
+    let content = if list.as_os_str() == "-" {
+        let mut content = Vec::new();
+        io::stdin().read_to_end(&mut content)?;
+        content
+    } else {
+        fs::read(list)?
+    };
+    Ok(parse_file_list(&content))
+}
+
+// Octets bruts, non UTF-8 compris : un NUL dans la liste la fait découper sur NUL (un nom
+// peut alors contenir un saut de ligne), sinon sur \n avec un éventuel \r final
+fn parse_file_list(content: &[u8]) -> Vec<PathBuf> {
+    let separator = if content.contains(&b'\0') {
+        b'\0'
+    } else {
+        b'\n'
+    };
+    content
+        .split(|&b| b == separator)
+        .map(|entry| match separator {
+            b'\n' => entry.strip_suffix(b"\r").unwrap_or(entry),
+            _ => entry,
+        })
+        .filter(|entry| !entry.is_empty())
+        .map(path_from_bytes)
+        .collect()
+}
+
+// --------------------------------------------------------------------------------
+// SORTIE (mise en forme du manifeste)
+// --------------------------------------------------------------------------------
+
+// Résultat du hachage d'un fichier, avant mise en forme
+struct FileRecord {
+    path: PathBuf,
+    rel: PathBuf,
+    result: io::Result<FileDigest>,
+    // Digests des algorithmes supplémentaires (vide en cas d'erreur)
+    extra: Vec<FileDigest>,
+    elapsed: Duration,
+    // Cible d'un lien enregistré sans être suivi (--include-symlinks-as-entries)
+    link: Option<PathBuf>,
+}
+
+impl FileRecord {
+    fn elapsed_ms(&self) -> f64 {
+        self.elapsed.as_secs_f64() * 1000.0
+    }
+
+    // Débit en MB/s (décimal), None en cas d'erreur ou de durée nulle
+    fn throughput(&self) -> Option<f64> {
+        let digest = self.result.as_ref().ok()?;
+        let secs = self.elapsed.as_secs_f64();
+        (secs > 0.0).then(|| digest.size as f64 / 1e6 / secs)
+    }
+
+    fn manifest_path(&self, args: &Args) -> String {
+        recorded_path(&self.path, &self.rel, args)
+    }
+
+    fn manifest_bytes(&self, args: &Args) -> Vec<u8> {
+        recorded_bytes(&self.path, &self.rel, args)
+    }
+
+    // Taille modifiée pendant le hachage (fichier en cours d'écriture)
+    fn changed(&self) -> bool {
+        self.result.as_ref().is_ok_and(|digest| digest.changed)
+    }
+
+    // Digest calculé sur le contenu décompressé (--decompress et extension reconnue)
+    fn decompressed(&self, args: &Args) -> bool {
+        self.result.is_ok() && args.decompress && Compression::from_path(&self.path).is_some()
+    }
+}
+
+// Chemin enregistré pour les formats texte (JSON, CSV...) : un nom non UTF-8 n'y est pas
+// représentable, il est approché (U+FFFD) et signalé
+fn recorded_path(path: &Path, rel: &Path, args: &Args) -> String {
+    String::from_utf8(recorded_bytes(path, rel, args)).unwrap_or_else(|e| {
+        eprintln!(
+            "{} {} {}",
+            Msg::Warning.text(args.lang),
+            Msg::NonUtf8Path.text(args.lang),
+            path.display()
+        );
+        String::from_utf8_lossy(e.as_bytes()).into_owned()
+    })
+}
+
+// Chemin tel qu'enregistré dans le manifeste : selon --path-base, séparateurs /,
+// préfixe optionnel. `rel` est le chemin relatif à --source. Sous Unix, les octets
+// du nom sont repris tels quels, même hors UTF-8, pour que --check les retrouve.
+fn recorded_bytes(path: &Path, rel: &Path, args: &Args) -> Vec<u8> {
+    let recorded = match args.path_base {
+        PathBase::Source => rel.to_path_buf(),
+        PathBase::Cwd => match (absolute_path(path), std::env::current_dir()) {
+            (Ok(path), Ok(cwd)) => relative_to(&path, &cwd),
+            _ => path.to_path_buf(),
+        },
+        PathBase::Absolute => absolute_path(path).unwrap_or_else(|_| path.to_path_buf()),
+    };
+    // Hors Unix, slash_bytes passe par une chaîne : un nom non Unicode y est approché
+    if cfg!(not(unix)) && recorded.to_str().is_none() {
+        eprintln!(
+            "{} {} {}",
+            Msg::Warning.text(args.lang),
+            Msg::NonUnicodePath.text(args.lang),
+            path.display()
+        );
+    }
+    let mut bytes = args.path_prefix.as_bytes().to_vec();
+    bytes.extend(normalize_path_bytes(slash_bytes(&recorded), args));
+    bytes
+}
+
+// --normalize-paths : un nom non UTF-8 n'a pas de forme normale, il est gardé tel quel
+fn normalize_path_bytes(bytes: Vec<u8>, args: &Args) -> Vec<u8> {
+    let Ok(text) = std::str::from_utf8(&bytes) else {
+        return bytes;
+    };
+    match args.normalize_paths {
+        PathNormalization::Nfc => text.nfc().collect::<String>().into_bytes(),
+        PathNormalization::Nfd => text.nfd().collect::<String>().into_bytes(),
+        PathNormalization::None => bytes,
+    }
+}
+
+// Chemin absolu sans "." ni "..", résolus lexicalement (les liens ne sont pas suivis)
+fn absolute_path(path: &Path) -> io::Result<PathBuf> {
+    let mut normalized = PathBuf::new();
+    // Préfixe \\?\ retiré : C:\ et \\serveur\partage se comparent tels qu'écrits
+    for component in strip_long_path(&std::path::absolute(path)?).components() {
+        match component {
+            std::path::Component::CurDir => {}
+            std::path::Component::ParentDir => {
+                normalized.pop();
+            }
+            component => normalized.push(component),
+        }
+    }
+    Ok(normalized)
+}
+
+// Chemin de `path` relatif à `base` (tous deux absolus), remontant par ".." si besoin ;
+// sans racine commune (autre lecteur sous Windows), `path` est gardé tel quel
+fn relative_to(path: &Path, base: &Path) -> PathBuf {
+    let path: Vec<_> = path.components().collect();
+    let base: Vec<_> = base.components().collect();
+    let common = path.iter().zip(&base).take_while(|(a, b)| a == b).count();
+    if common == 0 {
+        return path.iter().collect();
+    }
+    let mut rel: PathBuf = base[common..].iter().map(|_| "..").collect();
+    rel.extend(&path[common..]);
+    rel
+}
+
+// Digest encodé selon --encoding, raccourci par --truncate
+fn format_digest(digest: &FileDigest, args: &Args) -> String {
+    let mut text = encode_digest(digest, args);
+    if let Some(len) = args.truncate {
+        text.truncate(len);
+    }
+    text
+}
+
+// Digest complet, pour --check qui compare sur la longueur enregistrée
+fn encode_digest(digest: &FileDigest, args: &Args) -> String {
+    match args.encoding {
+        Encoding::Hex if args.uppercase => digest.to_hex().to_ascii_uppercase(),
+        Encoding::Hex => digest.to_hex(),
+        Encoding::Base64 => BASE64.encode(&digest.bytes),
+    }
+}
+
+// --hmac-key : algorithmes utilisés (demandés, ou lus dans le manifeste par --check)
+fn check_hmac_algos(algos: &[HashAlgo], args: &Args) -> io::Result<()> {
+    if let Some(&algo) = algos.iter().find(|algo| !algo.supports_hmac()) {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!(
+                "--hmac-key {} {}",
+                Msg::NeedsCryptoAlgo.text(args.lang),
+                algo_name(algo)
+            ),
+        ));
+    }
+    // HMAC-BLAKE2b repose sur le type à sortie fixe de 64 octets
+    if args.blake2b_length != BLAKE2B_MAX_LEN && algos.contains(&HashAlgo::Blake2b) {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!(
+                "--blake2b-length {} --hmac-key",
+                Msg::IncompatibleWith.text(args.lang)
+            ),
+        ));
+    }
+    Ok(())
+}
+
+// --truncate : hexadécimal uniquement (un base64 tronqué ne se décode plus), hors SFV dont
+// le CRC32 fait toujours 8 caractères ; une longueur plus grande que le digest est sans effet
+fn check_truncate(args: &Args, len: usize) -> io::Result<()> {
+    let lang = args.lang;
+    let message = if len == 0 {
+        Msg::MustBeNonZero.text(lang).to_string()
+    } else if matches!(args.encoding, Encoding::Base64) {
+        Msg::NeedsHexEncoding.text(lang).to_string()
+    } else if matches!(args.format, OutputFormat::Sfv) {
+        format!("{} --format sfv", Msg::IncompatibleWith.text(lang))
+    } else {
+        for &algo in args.algo.iter().filter(|&&algo| args.hex_len(algo) < len) {
+            eprintln!(
+                "{} --truncate {len} {} {} ({} {}), {}",
+                Msg::Warning.text(lang),
+                Msg::ExceedsDigest.text(lang),
+                algo_name(algo),
+                args.hex_len(algo),
+                Msg::Characters.text(lang),
+                Msg::KeptWhole.text(lang)
+            );
+        }
+        return Ok(());
+    };
+    Err(io::Error::new(
+        io::ErrorKind::InvalidInput,
+        format!("--truncate {message}"),
+    ))
+}
+
+// Manifeste écrit au fil du hachage : l'en-tête (CSV) ou l'ouverture du tableau (JSON)
+// à la création, une entrée par fichier, puis la fermeture dans `finish`
+enum ManifestWriter<'a> {
+    Text(ChecksumWriter<'a>),
+    Json {
+        out: Box<dyn Write + 'a>,
+        count: usize,
+    },
+    Csv(csv::Writer<Box<dyn Write + 'a>>),
+    Sfv(ChecksumWriter<'a>),
+}
+
+impl<'a> ManifestWriter<'a> {
+    fn new(out: Box<dyn Write + 'a>, args: &Args) -> io::Result<Self> {
+        let checksum = |out| ChecksumWriter {
+            out,
+            hasher: args.manifest_checksum.then(Sha256::new),
+        };
+        Ok(match args.format {
+            OutputFormat::Text => {
+                let mut out = checksum(out);
+                if args.decompress {
+                    write!(out, "# {DECOMPRESS_MARKER} (.gz, .zst, .bz2)")?;
+                    out.write_all(line_end(args))?;
+                }
+                if let Some(head) = args.head {
+                    write!(out, "# {HEAD_MARKER} {head} (partial digests)")?;
+                    out.write_all(line_end(args))?;
+                }
+                if args.include_metadata_in_hash {
+                    write!(out, "# {METADATA_MARKER} (mode, uid, gid, size)")?;
+                    out.write_all(line_end(args))?;
+                }
+                if HMAC_KEY.get().is_some() {
+                    write!(out, "# {HMAC_MARKER} (keyed digests)")?;
+                    out.write_all(line_end(args))?;
+                }
+                ManifestWriter::Text(out)
+            }
+            OutputFormat::Json => ManifestWriter::Json { out, count: 0 },
+            OutputFormat::Csv => {
+                let terminator = match line_end(args) {
+                    b"\r\n" => csv::Terminator::CRLF,
+                    _ => csv::Terminator::Any(b'\n'),
+                };
+                let mut writer = csv::WriterBuilder::new()
+                    .terminator(terminator)
+                    .from_writer(out);
+                writer.write_record(csv_header(args))?;
+                ManifestWriter::Csv(writer)
+            }
+            OutputFormat::Sfv => {
+                let mut out = checksum(out);
+                let now = humantime::format_rfc3339_seconds(SystemTime::now());
+                write!(out, "; Generated by zhashgen on {now}")?;
+                out.write_all(line_end(args))?;
+                if args.decompress {
+                    write!(out, "; {DECOMPRESS_MARKER} (.gz, .zst, .bz2)")?;
+                    out.write_all(line_end(args))?;
+                }
+                if let Some(head) = args.head {
+                    write!(out, "; {HEAD_MARKER} {head} (partial digests)")?;
+                    out.write_all(line_end(args))?;
+                }
+                if args.include_metadata_in_hash {
+                    write!(out, "; {METADATA_MARKER} (mode, uid, gid, size)")?;
+                    out.write_all(line_end(args))?;
+                }
+                ManifestWriter::Sfv(out)
+            }
+        })
+    }
+
+    // Suite d'un manifeste existant (--append) : ni en-tête ni pied de page
+    fn appending(out: Box<dyn Write + 'a>, args: &Args) -> Self {
+        let out = ChecksumWriter { out, hasher: None };
+        match args.format {
+            OutputFormat::Sfv => ManifestWriter::Sfv(out),
+            _ => ManifestWriter::Text(out),
+        }
+    }
+
+    fn write(&mut self, record: &FileRecord, errors: &mut ErrorLog, args: &Args) -> io::Result<()> {
+        match (self, &record.result) {
+            (ManifestWriter::Text(out), Ok(digest)) => write_text(out, record, digest, args),
+            (ManifestWriter::Sfv(out), Ok(digest)) => write_sfv(out, record, digest, args),
+            // Les lignes texte et SFV ne contiennent que des digests : erreurs à part
+            (ManifestWriter::Text(_) | ManifestWriter::Sfv(_), Err(e)) => {
+                errors.write(record, e, args)
+            }
+            (ManifestWriter::Json { out, count }, _) => {
+                write_json(out, record, *count, args)?;
+                *count += 1;
+                Ok(())
+            }
+            (ManifestWriter::Csv(writer), _) => Ok(writer.write_record(csv_row(record, args))?),
+        }
+    }
+
+    fn finish(self, args: &Args) -> io::Result<()> {
+        match self {
+            ManifestWriter::Text(out) => out.finish("#", line_end(args)),
+            ManifestWriter::Sfv(out) => out.finish(";", line_end(args)),
+            ManifestWriter::Json { mut out, count } => {
+                // Même rendu que serde_json::to_writer_pretty sur le tableau complet
+                if count > 0 {
+                    out.write_all(line_end(args))?;
+                }
+                write!(out, "{}", if count == 0 { "[]" } else { "]" })?;
+                out.write_all(line_end(args))?;
+                out.flush()
+            }
+            ManifestWriter::Csv(mut writer) => writer.flush(),
+        }
+    }
+}
+
+// Destination des enregistrements : un manifeste unique, ou un par répertoire (--per-dir)
+enum ManifestOutput<'a> {
+    Single(ManifestWriter<'a>),
+    PerDir(PerDirWriter<'a>),
+}
+
+impl ManifestOutput<'_> {
+    fn write(
+        &mut self,
+        record: &mut FileRecord,
+        errors: &mut ErrorLog,
+        args: &Args,
+    ) -> io::Result<()> {
+        match self {
+            ManifestOutput::Single(writer) => writer.write(record, errors, args),
+            ManifestOutput::PerDir(writer) => writer.write(record, errors, args),
+        }
+    }
+
+    // Nombre de manifestes écrits
+    fn finish(self, args: &Args) -> io::Result<usize> {
+        match self {
+            ManifestOutput::Single(writer) => writer.finish(args).map(|()| 1),
+            ManifestOutput::PerDir(writer) => writer.finish(args),
+        }
+    }
+}
+
+// --per-dir : les fichiers arrivent groupés par répertoire (tri de hash_files), le
+// manifeste du répertoire précédent est terminé dès qu'un autre commence
+struct PerDirWriter<'a> {
+    root: &'a Path,
+    current: Option<(PathBuf, ManifestWriter<'static>)>,
+    count: usize,
+}
+
+impl PerDirWriter<'_> {
+    fn write(
+        &mut self,
+        record: &mut FileRecord,
+        errors: &mut ErrorLog,
+        args: &Args,
+    ) -> io::Result<()> {
+        let dir = record.rel.parent().unwrap_or(Path::new("")).to_path_buf();
+        if self
+            .current
+            .as_ref()
+            .is_none_or(|(current, _)| *current != dir)
+        {
+            if let Some((_, writer)) = self.current.take() {
+                writer.finish(args)?;
+            }
+            let path = self.root.join(&dir).join(&args.name);
+            let out = Box::new(BufWriter::new(File::create(long_path(&path))?));
+            self.current = Some((dir.clone(), ManifestWriter::new(out, args)?));
+            self.count += 1;
+        }
+        let Some((_, writer)) = &mut self.current else {
+            unreachable!()
+        };
+        // Chemin relatif au répertoire du manifeste le temps de l'écriture ; le chemin
+        // relatif à --source reste celui des doublons et des statistiques
+        let local = record
+            .rel
+            .strip_prefix(&dir)
+            .unwrap_or(&record.rel)
+            .to_path_buf();
+        let rel = std::mem::replace(&mut record.rel, local);
+        let result = writer.write(record, errors, args);
+        record.rel = rel;
+        result
+    }
+
+    fn finish(self, args: &Args) -> io::Result<usize> {
+        if let Some((_, writer)) = self.current {
+            writer.finish(args)?;
+        }
+        Ok(self.count)
+    }
+}
+
+// Manifeste complété par --append : chemins déjà enregistrés
+struct Appended {
+    paths: HashSet<Vec<u8>>,
+    // Manifeste absent ou vide : écrit depuis le début, avec son en-tête
+    empty: bool,
+    // Le fichier ne se termine pas par un saut de ligne
+    unterminated: bool,
+}
+
+// Un manifeste absent est créé par --append comme par une génération normale.
+// Seuls les formats texte et SFV sont prolongeables ligne à ligne.
+fn read_appended(path: &Path, args: &Args) -> io::Result<Appended> {
+    let sfv = match args.format {
+        OutputFormat::Text => false,
+        OutputFormat::Sfv => true,
+        OutputFormat::Json | OutputFormat::Csv => {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("--append {}", Msg::NeedsTextOrSfv.text(args.lang)),
+            ));
+        }
+    };
+    let content = match fs::read(path) {
+        Ok(content) => content,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Vec::new(),
+        Err(e) => return Err(e),
+    };
+    let parse = if sfv {
+        parse_sfv_line
+    } else {
+        parse_manifest_line
+    };
+    let paths = content
+        .split(|&b| b == b'\n')
+        .filter_map(parse)
+        .map(|(_, _, path)| path.to_vec())
+        .collect();
+    Ok(Appended {
+        paths,
+        empty: content.is_empty(),
+        unterminated: !content.is_empty() && !content.ends_with(b"\n"),
+    })
+}
+
+// Sortie texte et SFV : les octets écrits sont hachés au passage pour --manifest-checksum
+struct ChecksumWriter<'a> {
+    out: Box<dyn Write + 'a>,
+    hasher: Option<Sha256>,
+}
+
+impl Write for ChecksumWriter<'_> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let n = self.out.write(buf)?;
+        if let Some(hasher) = &mut self.hasher {
+            hasher.update(&buf[..n]);
+        }
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.out.flush()
+    }
+}
+
+impl ChecksumWriter<'_> {
+    // Pied de page « <commentaire> manifest-sha256: <digest> », ignoré comme tout commentaire
+    fn finish(mut self, comment: &str, line_end: &[u8]) -> io::Result<()> {
+        if let Some(hasher) = self.hasher.take() {
+            let digest: String = hasher
+                .finalize()
+                .iter()
+                .map(|b| format!("{b:02x}"))
+                .collect();
+            write!(self.out, "{comment} {MANIFEST_CHECKSUM_TAG} {digest}")?;
+            self.out.write_all(line_end)?;
+        }
+        self.out.flush()
+    }
+}
+
+const MANIFEST_CHECKSUM_TAG: &str = "manifest-sha256:";
+
+// Commentaire d'en-tête (# en texte, ; en SFV) des manifestes générés avec --decompress
+const DECOMPRESS_MARKER: &str = "zhashgen: decompressed";
+
+// Commentaire d'en-tête des manifestes --head, suivi du nombre d'octets hachés par fichier
+const HEAD_MARKER: &str = "zhashgen: head";
+
+// Commentaire d'en-tête des manifestes --include-metadata-in-hash
+const METADATA_MARKER: &str = "zhashgen: metadata in hash";
+
+// Commentaire d'en-tête des manifestes --hmac-key (la clé n'y figure pas)
+const HMAC_MARKER: &str = "zhashgen: hmac";
+
+// Format standard du fichier de checksum (digest *chemin ou digest  chemin)
+fn write_text(
+    out: &mut impl Write,
+    record: &FileRecord,
+    digest: &FileDigest,
+    args: &Args,
+) -> io::Result<()> {
+    if let Some(template) = &args.template {
+        return write_template(out, template, record, digest, args);
+    }
+    let separator = match args.style {
+        OutputStyle::Legacy => " *",
+        OutputStyle::Gnu => "  ",
+        OutputStyle::Bsd => return write_bsd(out, record, digest, args),
+    };
+    write!(out, "{}{}", format_digest(digest, args), separator)?;
+    let path = record.manifest_bytes(args);
+    out.write_all(&path)?;
+    // Rien à aligner sans colonne après le chemin : pas d'espaces en fin de ligne
+    let columns = args.with_meta || !args.extra_algos().is_empty();
+    let columns = columns || record.link.is_some() || record.changed();
+    if let Some(&width) = PATH_WIDTH.get().filter(|_| columns) {
+        let len = String::from_utf8_lossy(&path).chars().count();
+        write!(out, "{:1$}", "", width.saturating_sub(len))?;
+    }
+    write_meta(out, digest, args)?;
+    for (&algo, extra) in args.extra_algos().iter().zip(&record.extra) {
+        write!(out, "\t{}:{}", algo_name(algo), format_digest(extra, args))?;
+    }
+    write_link(out, record)?;
+    write_changed(out, record)?;
+    out.write_all(line_end(args))
+}
+
+// Plus long chemin enregistré (--align)
+fn path_width(files: &[PathBuf], args: &Args) -> usize {
+    files
+        .iter()
+        .map(|path| {
+            let rel = path.strip_prefix(&args.source).unwrap_or(path);
+            String::from_utf8_lossy(&recorded_bytes(path, rel, args))
+                .chars()
+                .count()
+        })
+        .max()
+        .unwrap_or(0)
+}
+
+// Lignes « ALGO (chemin) = digest », une par algorithme demandé
+fn write_bsd(
+    out: &mut impl Write,
+    record: &FileRecord,
+    digest: &FileDigest,
+    args: &Args,
+) -> io::Result<()> {
+    let path = record.manifest_bytes(args);
+    let digests = std::iter::once(digest).chain(&record.extra);
+    for (&algo, digest) in args.algo.iter().zip(digests) {
+        write!(out, "{} (", algo.tag())?;
+        out.write_all(&path)?;
+        write!(out, ") = {}", format_digest(digest, args))?;
+        write_meta(out, digest, args)?;
+        write_link(out, record)?;
+        write_changed(out, record)?;
+        out.write_all(line_end(args))?;
+    }
+    Ok(())
+}
+
+// Ligne --template : analysée au démarrage, un champ inconnu y est refusé
+#[derive(Clone)]
+struct Template(Vec<TemplatePart>);
+
+#[derive(Clone)]
+enum TemplatePart {
+    Literal(String),
+    Digest,
+    Path,
+    Size,
+    Algo,
+    Mtime,
+}
+
+fn parse_template(input: &str) -> Result<Template, String> {
+    let lang = current_lang();
+    let mut parts = Vec::new();
+    let mut literal = String::new();
+    let mut chars = input.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '{' if chars.as_str().starts_with('{') => {
+                chars.next();
+                literal.push('{');
+            }
+            '}' if chars.as_str().starts_with('}') => {
+                chars.next();
+                literal.push('}');
+            }
+            '{' => {
+                let rest = chars.as_str();
+                let Some(end) = rest.find('}') else {
+                    return Err(format!(
+                        "{} {} '{input}'",
+                        Msg::InvalidTemplate.text(lang),
+                        Msg::UnclosedBrace.text(lang)
+                    ));
+                };
+                let part = match &rest[..end] {
+                    "digest" => TemplatePart::Digest,
+                    "path" => TemplatePart::Path,
+                    "size" => TemplatePart::Size,
+                    "algo" => TemplatePart::Algo,
+                    "mtime" => TemplatePart::Mtime,
+                    other => {
+                        return Err(format!(
+                            "{} {{{other}}} ({})",
+                            Msg::UnknownTemplateField.text(lang),
+                            Msg::TemplateFields.text(lang)
+                        ));
+                    }
+                };
+                chars = rest[end + 1..].chars();
+                if !literal.is_empty() {
+                    parts.push(TemplatePart::Literal(std::mem::take(&mut literal)));
+                }
+                parts.push(part);
+            }
+            '}' => {
+                return Err(format!(
+                    "{} {} '{input}'",
+                    Msg::InvalidTemplate.text(lang),
+                    Msg::StrayBrace.text(lang)
+                ));
+            }
+            c => literal.push(c),
+        }
+    }
+    if !literal.is_empty() {
+        parts.push(TemplatePart::Literal(literal));
+    }
+    Ok(Template(parts))
+}
+
+// Lignes --template, une par algorithme demandé (comme --style bsd)
+fn write_template(
+    out: &mut impl Write,
+    template: &Template,
+    record: &FileRecord,
+    digest: &FileDigest,
+    args: &Args,
+) -> io::Result<()> {
+    let path = record.manifest_bytes(args);
+    let digests = std::iter::once(digest).chain(&record.extra);
+    for (&algo, digest) in args.algo.iter().zip(digests) {
+        for part in &template.0 {
+            match part {
+                TemplatePart::Literal(text) => out.write_all(text.as_bytes())?,
+                TemplatePart::Digest => out.write_all(format_digest(digest, args).as_bytes())?,
+                TemplatePart::Path => out.write_all(&path)?,
+                TemplatePart::Size => write!(out, "{}", digest.size)?,
+                TemplatePart::Algo => out.write_all(algo_name(algo).as_bytes())?,
+                TemplatePart::Mtime => {
+                    let modified = format_modified(digest).unwrap_or_else(|| "-".to_string());
+                    out.write_all(modified.as_bytes())?
+                }
+            }
+        }
+        out.write_all(line_end(args))?;
+    }
+    Ok(())
+}
+
+// Colonne « symlink -> cible » d'un lien enregistré comme tel, ignorée par --check
+fn write_link(out: &mut impl Write, record: &FileRecord) -> io::Result<()> {
+    match &record.link {
+        Some(target) => write!(out, "\tsymlink -> {}", target.display()),
+        None => Ok(()),
+    }
+}
+
+// Colonne « changed-during-scan », ignorée par --check comme les autres colonnes
+fn write_changed(out: &mut impl Write, record: &FileRecord) -> io::Result<()> {
+    if record.changed() {
+        write!(out, "\t{CHANGED_DURING_SCAN}")?;
+    }
+    Ok(())
+}
+
+const CHANGED_DURING_SCAN: &str = "changed-during-scan";
+
+// Colonnes --with-meta (taille, date de modification) après une tabulation
+fn write_meta(out: &mut impl Write, digest: &FileDigest, args: &Args) -> io::Result<()> {
+    if args.with_meta {
+        let modified = format_modified(digest).unwrap_or_else(|| "-".to_string());
+        write!(out, "\t{}\t{}", digest.size, modified)?;
+    }
+    Ok(())
+}
+
+// Ligne SFV « chemin CRC32 »
+fn write_sfv(
+    out: &mut impl Write,
+    record: &FileRecord,
+    digest: &FileDigest,
+    args: &Args,
+) -> io::Result<()> {
+    out.write_all(&record.manifest_bytes(args))?;
+    write!(out, " {}", digest.to_hex().to_ascii_uppercase())?;
+    out.write_all(line_end(args))
+}
+
+// Fichier des erreurs à côté du manifeste : checksums.txt -> checksums.txt.errors
+// (None : stderr)
+fn errors_path(args: &Args, output_file: Option<&Path>) -> Option<PathBuf> {
+    match &args.errors_file {
+        Some(path) if path.as_os_str() == "-" => None,
+        Some(path) => Some(path.clone()),
+        None => output_file.map(|path| {
+            let mut name = path.as_os_str().to_owned();
+            name.push(".errors");
+            PathBuf::from(name)
+        }),
+    }
+}
+
+// Erreurs de hachage écartées des manifestes texte et SFV (--errors-file). Le fichier
+// n'est créé qu'à la première erreur ; celui d'un passage précédent est supprimé au
+// départ, sauf avec --append qui le complète.
+struct ErrorLog {
+    path: Option<PathBuf>,
+    out: Option<BufWriter<File>>,
+    append: bool,
+}
+
+impl ErrorLog {
+    fn new(path: Option<PathBuf>, args: &Args) -> io::Result<Self> {
+        if let Some(path) = path.as_deref().filter(|_| !args.append) {
+            match fs::remove_file(path) {
+                Err(e) if e.kind() != io::ErrorKind::NotFound => return Err(e),
+                _ => {}
+            }
+        }
+        Ok(ErrorLog {
+            path,
+            out: None,
+            append: args.append,
+        })
+    }
+
+    fn write(&mut self, record: &FileRecord, e: &io::Error, args: &Args) -> io::Result<()> {
+        let line = format!(
+            "[ERROR] {}: {} ({})",
+            record.path.display(),
+            e,
+            ErrorClass::of(e).label()
+        );
+        let Some(path) = &self.path else {
+            eprintln!("{line}");
+            return Ok(());
+        };
+        let out = match &mut self.out {
+            Some(out) => out,
+            None => {
+                let file = fs::OpenOptions::new()
+                    .write(true)
+                    .create(true)
+                    .append(self.append)
+                    .truncate(!self.append)
+                    .open(path)?;
+                self.out.insert(BufWriter::new(file))
+            }
+        };
+        out.write_all(line.as_bytes())?;
+        out.write_all(line_end(args))
+    }
+
+    // Chemin du fichier s'il a reçu au moins une erreur
+    fn finish(self) -> io::Result<Option<PathBuf>> {
+        match self.out {
+            Some(mut out) => {
+                out.flush()?;
+                Ok(self.path)
+            }
+            None => Ok(None),
+        }
+    }
+}
+
+// Date de modification au format RFC3339 (UTC, à la seconde)
+fn format_modified(digest: &FileDigest) -> Option<String> {
+    digest
+        .modified
+        .map(|time| humantime::format_rfc3339_seconds(time).to_string())
+}
+
+// Nom clap de l'algorithme (xxh3, sha256...), pour les colonnes et champs par algorithme
+fn algo_name(algo: HashAlgo) -> String {
+    algo.to_possible_value()
+        .map(|v| v.get_name().to_string())
+        .unwrap_or_default()
+}
+
+// Chemin avec des séparateurs / quelle que soit la plateforme
+fn slash_path(path: &Path) -> String {
+    path.display().to_string().replace('\\', "/")
+}
+
+// Comme slash_path, sans perte sous Unix pour les noms non UTF-8
+#[cfg(unix)]
+fn slash_bytes(path: &Path) -> Vec<u8> {
+    use std::os::unix::ffi::OsStrExt;
+    let separator = |&b: &u8| if b == b'\\' { b'/' } else { b };
+    path.as_os_str().as_bytes().iter().map(separator).collect()
+}
+
+#[cfg(not(unix))]
+fn slash_bytes(path: &Path) -> Vec<u8> {
+    slash_path(path).into_bytes()
+}
+
+#[derive(Serialize)]
+struct JsonRecord {
+    path: String,
+    digest: Option<String>,
+    size: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    modified: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    elapsed_ms: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    mb_per_s: Option<f64>,
+    // Algorithmes supplémentaires de --algo : nom -> digest
+    #[serde(skip_serializing_if = "Option::is_none")]
+    digests: Option<BTreeMap<String, String>>,
+    // Digest du contenu décompressé (--decompress)
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    decompressed: bool,
+    // Cible d'un lien enregistré sans être suivi
+    #[serde(skip_serializing_if = "Option::is_none")]
+    symlink: Option<String>,
+    // Taille modifiée pendant le hachage : `size` est le nombre d'octets hachés
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    changed_during_scan: bool,
+    // Digest partiel des N premiers octets (--head)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    head_bytes: Option<u64>,
+    // Digest des attributs et du contenu (--include-metadata-in-hash)
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    metadata_in_hash: bool,
+    error: Option<String>,
+    // not-found, permission-denied ou io
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error_kind: Option<&'static str>,
+}
+
+// Élément du tableau JSON : les erreurs sont portées par le champ `error` (digest à null).
+// `index` est la position dans le tableau, pour le séparateur.
+fn write_json(
+    out: &mut impl Write,
+    record: &FileRecord,
+    index: usize,
+    args: &Args,
+) -> io::Result<()> {
+    let json = match &record.result {
+        Ok(digest) => JsonRecord {
+            path: record.manifest_path(args),
+            digest: Some(format_digest(digest, args)),
+            size: digest.size,
+            modified: format_modified(digest).filter(|_| args.with_meta),
+            elapsed_ms: args.timings.then(|| record.elapsed_ms()),
+            mb_per_s: record.throughput().filter(|_| args.timings),
+            digests: (!args.extra_algos().is_empty()).then(|| {
+                args.extra_algos()
+                    .iter()
+                    .zip(&record.extra)
+                    .map(|(&algo, extra)| (algo_name(algo), format_digest(extra, args)))
+                    .collect()
+            }),
+            decompressed: record.decompressed(args),
+            symlink: record.link.as_ref().map(|t| t.display().to_string()),
+            changed_during_scan: digest.changed,
+            head_bytes: args.head,
+            metadata_in_hash: args.include_metadata_in_hash,
+            error: None,
+            error_kind: None,
+        },
+        Err(e) => JsonRecord {
+            path: record.manifest_path(args),
+            digest: None,
+            size: 0,
+            modified: None,
+            elapsed_ms: args.timings.then(|| record.elapsed_ms()),
+            mb_per_s: None,
+            digests: None,
+            decompressed: false,
+            symlink: None,
+            changed_during_scan: false,
+            head_bytes: None,
+            metadata_in_hash: false,
+            error: Some(e.to_string()),
+            error_kind: Some(ErrorClass::of(e).label()),
+        },
+    };
+    // Chaque objet est indenté d'un niveau, comme dans un tableau sérialisé d'un bloc
+    let object = serde_json::to_string_pretty(&json)?;
+    write!(out, "{}", if index == 0 { "[" } else { "," })?;
+    for line in object.lines() {
+        out.write_all(line_end(args))?;
+        write!(out, "  {line}")?;
+    }
+    Ok(())
+}
+
+// CSV avec en-tête ; en cas d'erreur le digest est vide et le message va dans `error`
+fn csv_header(args: &Args) -> Vec<String> {
+    let mut header: Vec<String> = ["path", "algo", "digest", "size_bytes", "error"]
+        .map(String::from)
+        .to_vec();
+    if args.with_meta {
+        header.push("modified".to_string());
+    }
+    if args.timings {
+        header.extend(["elapsed_ms".to_string(), "mb_per_s".to_string()]);
+    }
+    if args.decompress {
+        header.push("decompressed".to_string());
+    }
+    if args.head.is_some() {
+        header.push("head_bytes".to_string());
+    }
+    if args.include_metadata_in_hash {
+        header.push("metadata_in_hash".to_string());
+    }
+    if args.include_symlinks_as_entries {
+        header.push("symlink".to_string());
+    }
+    header.push("changed_during_scan".to_string());
+    // Une colonne digest_<algo> par algorithme supplémentaire
+    header.extend(
+        args.extra_algos()
+            .iter()
+            .map(|&algo| format!("digest_{}", algo_name(algo))),
+    );
+    header
+}
+
+fn csv_row(record: &FileRecord, args: &Args) -> Vec<String> {
+    let path = record.manifest_path(args);
+    let algo = algo_name(args.primary_algo());
+    let mut row = match &record.result {
+        Ok(digest) => vec![
+            path,
+            algo,
+            format_digest(digest, args),
+            digest.size.to_string(),
+            String::new(),
+        ],
+        Err(e) => vec![path, algo, String::new(), "0".to_string(), e.to_string()],
+    };
+    if args.with_meta {
+        let modified = record.result.as_ref().ok().and_then(format_modified);
+        row.push(modified.unwrap_or_default());
+    }
+    if args.timings {
+        row.push(format!("{:.3}", record.elapsed_ms()));
+        row.push(
+            record
+                .throughput()
+                .map(|t| format!("{t:.2}"))
+                .unwrap_or_default(),
+        );
+    }
+    if args.decompress {
+        row.push(record.decompressed(args).to_string());
+    }
+    if let Some(head) = args.head {
+        row.push(head.to_string());
+    }
+    if args.include_metadata_in_hash {
+        row.push(record.result.is_ok().to_string());
+    }
+    if args.include_symlinks_as_entries {
+        let target = record.link.as_ref().map(|t| t.display().to_string());
+        row.push(target.unwrap_or_default());
+    }
+    row.push(record.changed().to_string());
+    for i in 0..args.extra_algos().len() {
+        let extra = record
+            .extra
+            .get(i)
+            .map(|digest| format_digest(digest, args));
+        row.push(extra.unwrap_or_default());
+    }
+    row
+}
+
+// --------------------------------------------------------------------------------
+// DOUBLONS (--find-dupes)
+// --------------------------------------------------------------------------------
+
+// Groupes d'au moins deux fichiers de même digest et même taille, dans l'ordre du manifeste.
+// Les liens enregistrés comme tels (--include-symlinks-as-entries) en sont exclus : leur
+// digest est celui du chemin cible, pas d'un contenu.
+fn find_duplicates(records: &[FileRecord]) -> Vec<Vec<&FileRecord>> {
+    let mut groups: HashMap<(&[u8], u64), Vec<&FileRecord>> = HashMap::new();
+    for record in records {
+        if let Ok(digest) = &record.result
+            && record.link.is_none()
+        {
+            groups
+                .entry((&digest.bytes, digest.size))
+                .or_default()
+                .push(record);
+        }
+    }
+    let mut groups: Vec<_> = groups.into_values().filter(|g| g.len() > 1).collect();
+    groups.sort_by_cached_key(|group| slash_path(&group[0].rel));
+    groups
+}
+
+// --sort / --reverse sur des résultats arrivés dans l'ordre des chemins ; à clé égale, cet
+// ordre est conservé (tri stable). Les fichiers en erreur vont en fin de manifeste (en
+// tête avec --reverse).
+fn sort_records(records: &mut [FileRecord], args: &Args) {
+    match args.sort {
+        SortKey::Path => {}
+        SortKey::Size => records.sort_by_key(|record| {
+            std::cmp::Reverse(record.result.as_ref().ok().map(|digest| digest.size))
+        }),
+        SortKey::Digest => records.sort_by(|a, b| match (&a.result, &b.result) {
+            (Ok(a), Ok(b)) => a.bytes.cmp(&b.bytes),
+            (Ok(_), Err(_)) => std::cmp::Ordering::Less,
+            (Err(_), Ok(_)) => std::cmp::Ordering::Greater,
+            (Err(_), Err(_)) => std::cmp::Ordering::Equal,
+        }),
+    }
+    if args.reverse {
+        records.reverse();
+    }
+}
+
+// Digest écrit (raccourci par --truncate le cas échéant) partagé par deux fichiers de
+// tailles différentes : les contenus diffèrent forcément, c'est une collision et non un
+// doublon. Seul le premier fichier de chaque digest est conservé pour comparaison.
+#[derive(Default)]
+struct CollisionCheck {
+    first: HashMap<String, (u64, PathBuf)>,
+    found: Vec<(String, PathBuf, PathBuf)>,
+}
+
+// Collisions détaillées sur stderr au-delà desquelles seul leur nombre est donné
+const MAX_LISTED_COLLISIONS: usize = 10;
+
+impl CollisionCheck {
+    fn add(&mut self, digest: String, size: u64, rel: &Path) {
+        match self.first.get(&digest) {
+            Some((first_size, first)) if *first_size != size => {
+                self.found.push((digest, first.clone(), rel.to_path_buf()));
+            }
+            Some(_) => {}
+            None => {
+                self.first.insert(digest, (size, rel.to_path_buf()));
+            }
+        }
+    }
+
+    fn warn(&self, file_count: usize, lang: Lang) {
+        if self.found.is_empty() {
+            return;
+        }
+        eprintln!(
+            "{} {} {} {file_count} {}{}",
+            Msg::Warning.text(lang),
+            self.found.len(),
+            Msg::Collisions.text(lang),
+            Msg::Files.text(lang),
+            Msg::WeakDigest.text(lang)
+        );
+        for (digest, first, other) in self.found.iter().take(MAX_LISTED_COLLISIONS) {
+            eprintln!("  {digest} : {} / {}", slash_path(first), slash_path(other));
+        }
+        if self.found.len() > MAX_LISTED_COLLISIONS {
+            eprintln!(
+                "  ... ({} {})",
+                self.found.len() - MAX_LISTED_COLLISIONS,
+                Msg::More.text(lang)
+            );
+        }
+    }
+}
+
+// Octets libérés en ne gardant qu'un exemplaire de chaque groupe
+fn reclaimable_bytes(groups: &[Vec<&FileRecord>]) -> u64 {
+    groups
+        .iter()
+        .map(|group| {
+            let size = group[0].result.as_ref().map_or(0, |d| d.size);
+            size * (group.len() as u64 - 1)
+        })
+        .sum()
+}
+
+// Un bloc par groupe : « digest (N files, taille each) » puis les chemins indentés
+fn write_duplicates(
+    out: &mut impl Write,
+    groups: &[Vec<&FileRecord>],
+    args: &Args,
+) -> io::Result<()> {
+    let lang = args.lang;
+    for group in groups {
+        let Ok(digest) = &group[0].result else {
+            continue;
+        };
+        writeln!(
+            out,
+            "{} ({} {}, {} {})",
+            format_digest(digest, args),
+            group.len(),
+            Msg::Files.text(lang),
+            human_readable(digest.size),
+            Msg::Each.text(lang)
+        )?;
+        for record in group {
+            writeln!(out, "  {}", record.manifest_path(args))?;
+        }
+    }
+    writeln!(
+        out,
+        "{} {}, {} {}",
+        groups.len(),
+        Msg::DuplicateGroups.text(lang),
+        human_readable(reclaimable_bytes(groups)),
+        Msg::Reclaimable.text(lang)
+    )
+}
+
+// --------------------------------------------------------------------------------
+// LIENS PHYSIQUES (--hardlink-dupes)
+// --------------------------------------------------------------------------------
+
+// Doublon au contenu vérifié, à remplacer par un lien vers `canonical`
+struct LinkPlan<'a> {
+    canonical: &'a Path,
+    duplicate: &'a Path,
+    size: u64,
+    // Dates relevées à la comparaison : un fichier modifié depuis n'est pas touché
+    modified: [Option<SystemTime>; 2],
+}
+
+// Plan affiché sur stderr (stdout peut porter le manifeste), puis appliqué après
+// confirmation. Sans terminal ni --yes, rien n'est modifié.
+fn hardlink_duplicates(groups: &[Vec<&FileRecord>], args: &Args) -> io::Result<()> {
+    let lang = args.lang;
+    let plans: Vec<_> = groups
+        .par_iter()
+        .flat_map_iter(|group| plan_group(group, lang))
+        .collect();
+    let mut canonical = None;
+    for plan in &plans {
+        if canonical != Some(plan.canonical) {
+            eprintln!("{}", slash_path(plan.canonical));
+            canonical = Some(plan.canonical);
+        }
+        eprintln!("  = {}", slash_path(plan.duplicate));
+    }
+    let bytes = plans.iter().map(|plan| plan.size).sum();
+    eprintln!(
+        "{} {}, {} {}",
+        plans.len(),
+        Msg::HardlinksPlanned.text(lang),
+        human_readable(bytes),
+        Msg::Reclaimable.text(lang)
+    );
+    if plans.is_empty() {
+        return Ok(());
+    }
+
+    let confirmed = args.yes
+        || io::stdin().is_terminal() && {
+            eprint!("{} ", Msg::ConfirmHardlinks.text(lang));
+            let mut answer = String::new();
+            io::stdin().read_line(&mut answer)?;
+            matches!(
+                answer.trim().to_lowercase().as_str(),
+                "y" | "yes" | "o" | "oui"
+            )
+        };
+    if !confirmed {
+        eprintln!("{}", Msg::HardlinksPreviewOnly.text(lang));
+        return Ok(());
+    }
+
+    let (mut linked, mut freed) = (0usize, 0u64);
+    for plan in &plans {
+        match replace_with_link(plan, lang) {
+            Ok(()) => {
+                linked += 1;
+                freed += plan.size;
+            }
+            Err(e) => eprintln!(
+                "{} {} {} {e}",
+                Msg::Warning.text(lang),
+                plan.duplicate.display(),
+                Msg::NotReplacedByLink.text(lang)
+            ),
+        }
+    }
+    eprintln!(
+        "{linked} {}, {} {}",
+        Msg::HardlinksCreated.text(lang),
+        human_readable(freed),
+        Msg::Freed.text(lang)
+    );
+    Ok(())
+}
+
+// Le premier fichier du groupe est conservé. Les liens symboliques, enregistrés comme
+// tels ou suivis, ne sont ni conservés ni remplacés.
+fn plan_group<'a>(group: &[&'a FileRecord], lang: Lang) -> Vec<LinkPlan<'a>> {
+    let mut files = group.iter().filter(|record| {
+        record.link.is_none()
+            && fs::symlink_metadata(long_path(&record.path))
+                .is_ok_and(|meta| !meta.file_type().is_symlink())
+    });
+    let Some(canonical) = files.next() else {
+        return Vec::new();
+    };
+    files
+        .filter_map(
+            |duplicate| match plan_link(&canonical.path, &duplicate.path, lang) {
+                Ok(plan) => plan,
+                Err(e) => {
+                    eprintln!(
+                        "{} {} {} {e}",
+                        Msg::Warning.text(lang),
+                        duplicate.path.display(),
+                        Msg::NotLinked.text(lang)
+                    );
+                    None
+                }
+            },
+        )
+        .collect()
+}
+
+// None : déjà le même fichier, autre système de fichiers ou contenu différent
+fn plan_link<'a>(
+    canonical: &'a Path,
+    duplicate: &'a Path,
+    lang: Lang,
+) -> io::Result<Option<LinkPlan<'a>>> {
+    let meta = [
+        fs::metadata(long_path(canonical))?,
+        fs::metadata(long_path(duplicate))?,
+    ];
+    if file_id(canonical) == file_id(duplicate) {
+        info!(
+            "{} {} {}",
+            duplicate.display(),
+            Msg::AlreadyLinked.text(lang),
+            canonical.display()
+        );
+        return Ok(None);
+    }
+    if same_device(&meta[0], &meta[1]) == Some(false) {
+        eprintln!(
+            "{} {} {} {} {}",
+            Msg::Warning.text(lang),
+            duplicate.display(),
+            Msg::NotLinked.text(lang),
+            Msg::OtherFilesystem.text(lang),
+            canonical.display()
+        );
+        return Ok(None);
+    }
+    // Le digest seul ne suffit pas pour supprimer des données
+    if !same_content(canonical, duplicate)? {
+        eprintln!(
+            "{} {} {} {} {}",
+            Msg::Warning.text(lang),
+            duplicate.display(),
+            Msg::NotLinked.text(lang),
+            Msg::ContentDiffers.text(lang),
+            canonical.display()
+        );
+        return Ok(None);
+    }
+    Ok(Some(LinkPlan {
+        canonical,
+        duplicate,
+        size: meta[1].len(),
+        modified: meta.map(|meta| meta.modified().ok()),
+    }))
+}
+
+#[cfg(unix)]
+fn same_device(a: &fs::Metadata, b: &fs::Metadata) -> Option<bool> {
+    use std::os::unix::fs::MetadataExt;
+    Some(a.dev() == b.dev())
+}
+
+// Inconnu ailleurs : fs::hard_link échoue d'un volume à l'autre
+#[cfg(not(unix))]
+fn same_device(_: &fs::Metadata, _: &fs::Metadata) -> Option<bool> {
+    None
+}
+
+fn same_content(a: &Path, b: &Path) -> io::Result<bool> {
+    let mut files = [File::open(long_path(a))?, File::open(long_path(b))?];
+    if files[0].metadata()?.len() != files[1].metadata()?.len() {
+        return Ok(false);
+    }
+    let mut buffers = [Vec::new(), Vec::new()];
+    loop {
+        for (file, buffer) in files.iter_mut().zip(&mut buffers) {
+            buffer.clear();
+            file.take(DEFAULT_BUFFER_SIZE as u64).read_to_end(buffer)?;
+        }
+        if buffers[0] != buffers[1] {
+            return Ok(false);
+        }
+        if buffers[0].is_empty() {
+            return Ok(true);
+        }
+    }
+}
+
+// Lien créé à côté du doublon puis renommé par-dessus : le chemin ne disparaît jamais
+fn replace_with_link(plan: &LinkPlan, lang: Lang) -> io::Result<()> {
+    for (path, modified) in [plan.canonical, plan.duplicate].iter().zip(plan.modified) {
+        if fs::metadata(long_path(path))?.modified().ok() != modified {
+            return Err(io::Error::other(format!(
+                "{} {}",
+                path.display(),
+                Msg::ModifiedSinceComparison.text(lang)
+            )));
+        }
+    }
+    let mut name = std::ffi::OsString::from(".");
+    name.push(plan.duplicate.file_name().unwrap_or_default());
+    name.push(".zhashgen-link");
+    let temp = plan.duplicate.with_file_name(name);
+    fs::hard_link(long_path(plan.canonical), long_path(&temp))?;
+    fs::rename(long_path(&temp), long_path(plan.duplicate)).inspect_err(|_| {
+        let _ = fs::remove_file(long_path(&temp));
+    })
+}
+
+// --------------------------------------------------------------------------------
+// VERROU DU MANIFESTE
+// --------------------------------------------------------------------------------
+
+fn lock_path(output_file: &Path) -> PathBuf {
+    let mut name = output_file.as_os_str().to_owned();
+    name.push(".lock");
+    PathBuf::from(name)
+}
+
+// Verrou consultatif exclusif sur <manifeste>.lock : deux lancements sur le même manifeste
+// l'écraseraient l'un l'autre. Le verrou tombe avec le processus ; le fichier, supprimé
+// à la libération, ne reste qu'après un arrêt brutal et n'empêche pas un nouveau lancement.
+struct OutputLock {
+    file: File,
+    path: PathBuf,
+}
+
+impl OutputLock {
+    fn acquire(path: &Path, args: &Args) -> io::Result<Self> {
+        let mut file = File::create(path)?;
+        let locked = loop {
+            match file.try_lock_exclusive() {
+                // Verrouillé après sa suppression par le détenteur précédent : ce fichier
+                // ne protège plus rien, un autre lancement a pu recréer et verrouiller `path`
+                Ok(()) if !is_linked_at(&file, path) => file = File::create(path)?,
+                result => break result,
+            }
+        };
+        match locked {
+            Ok(()) => {}
+            Err(e) if args.force => {
+                eprintln!(
+                    "{} {} {} ({e})",
+                    Msg::Warning.text(args.lang),
+                    Msg::LockIgnored.text(args.lang),
+                    path.display()
+                );
+            }
+            Err(e) if e.kind() == fs2::lock_contended_error().kind() => {
+                return Err(io::Error::new(
+                    io::ErrorKind::WouldBlock,
+                    format!("{} {}", path.display(), Msg::Locked.text(args.lang)),
+                ));
+            }
+            Err(e) => return Err(e),
+        }
+        Ok(OutputLock {
+            file,
+            path: path.to_path_buf(),
+        })
+    }
+}
+
+// `file` est-il encore le fichier désigné par `path` ? Hors Unix, un fichier supprimé mais
+// encore ouvert ne peut pas être recréé : deux verrous ne peuvent pas coexister
+#[cfg(unix)]
+fn is_linked_at(file: &File, path: &Path) -> bool {
+    use std::os::unix::fs::MetadataExt;
+    let locked = file.metadata().ok().map(|meta| (meta.dev(), meta.ino()));
+    locked.is_some() && locked == file_id(path)
+}
+
+#[cfg(not(unix))]
+fn is_linked_at(_file: &File, _path: &Path) -> bool {
+    true
+}
+
+impl Drop for OutputLock {
+    fn drop(&mut self) {
+        // Supprimé avant déverrouillage : un lancement concurrent recrée son propre fichier
+        let _ = fs::remove_file(&self.path);
+        let _ = self.file.unlock();
+    }
+}
+
+// --------------------------------------------------------------------------------
+// MISE À JOUR INCRÉMENTALE (--update)
+// --------------------------------------------------------------------------------
+
+// Empreinte d'un fichier lors du précédent passage
+struct StateEntry {
+    size: u64,
+    modified: u128,
+    // Un digest par algorithme de --algo, dans le même ordre
+    digests: Vec<Vec<u8>>,
+}
+
+// Fichier d'état à côté du manifeste : checksums.txt -> checksums.txt.state
+fn state_path(output_file: &Path) -> PathBuf {
+    let mut name = output_file.as_os_str().to_owned();
+    name.push(".state");
+    PathBuf::from(name)
+}
+
+// Date de modification en nanosecondes depuis l'époque Unix (comparaison exacte)
+fn mtime_nanos(modified: SystemTime) -> Option<u128> {
+    modified
+        .duration_since(UNIX_EPOCH)
+        .ok()
+        .map(|d| d.as_nanos())
+}
+
+// Une ligne par fichier : « taille mtime_ns digest_hex[,digest_hex...] chemin ». L'en-tête
+// mémorise les algorithmes et leurs paramètres : un état produit autrement est ignoré.
+fn load_state(
+    path: &Path,
+    algos: &[HashAlgo],
+    config: &HashConfig,
+    lang: Lang,
+) -> io::Result<HashMap<String, StateEntry>> {
+    let content = match fs::read_to_string(path) {
+        Ok(content) => content,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(HashMap::new()),
+        Err(e) => return Err(e),
+    };
+    let mut lines = content.lines();
+    if lines.next() != Some(&state_header(algos, config)) {
+        return Ok(HashMap::new());
+    }
+    let invalid = || {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("{} {}", Msg::CorruptedState.text(lang), path.display()),
+        )
+    };
+    lines
+        .map(|line| {
+            let mut fields = line.splitn(4, ' ');
+            let mut next = || fields.next().ok_or_else(invalid);
+            let size = next()?.parse().map_err(|_| invalid())?;
+            let modified = next()?.parse().map_err(|_| invalid())?;
+            let digests = next()?
+                .split(',')
+                .map(decode_hex)
+                .collect::<Option<Vec<_>>>()
+                .filter(|digests| digests.len() == algos.len())
+                .ok_or_else(invalid)?;
+            let rel = next()?.to_string();
+            Ok((
+                rel,
+                StateEntry {
+                    size,
+                    modified,
+                    digests,
+                },
+            ))
+        })
+        .collect()
+}
+
+fn save_state<'a>(
+    path: &Path,
+    algos: &[HashAlgo],
+    config: &HashConfig,
+    records: impl IntoIterator<Item = &'a FileRecord>,
+) -> io::Result<()> {
+    let mut out = BufWriter::new(File::create(path)?);
+    writeln!(out, "{}", state_header(algos, config))?;
+    for record in records {
+        // Les erreurs ne sont pas mémorisées : le fichier sera retenté au prochain passage
+        if let Ok(digest) = &record.result
+            && let Some(modified) = digest.modified.and_then(mtime_nanos)
+        {
+            let hexes: Vec<_> = std::iter::once(digest)
+                .chain(&record.extra)
+                .map(FileDigest::to_hex)
+                .collect();
+            writeln!(
+                out,
+                "{} {} {} {}",
+                digest.size,
+                modified,
+                hexes.join(","),
+                slash_path(&record.rel)
+            )?;
+        }
+    }
+    out.flush()
+}
+
+// Algorithmes suivis de chaque paramètre qui change leurs digests, omis à sa valeur par
+// défaut : un état v1 antérieur à ces paramètres reste valable
+fn state_header(algos: &[HashAlgo], config: &HashConfig) -> String {
+    let names: Vec<_> = algos.iter().map(|algo| algo.extension()).collect();
+    let mut header = format!("# zhashgen state v1 {}", names.join(","));
+    if config.decompress {
+        header.push_str(" decompress");
+    }
+    if let Some(table) = config
+        .crc32_table
+        .filter(|_| algos.contains(&HashAlgo::Crc32))
+    {
+        let spec = table.spec();
+        header.push_str(&format!(
+            " crc32={:08X}/{:08X}/{:08X}/{}",
+            spec.poly, spec.init, spec.xorout, spec.reflected
+        ));
+    }
+    if let Some(variant) = config
+        .crc16
+        .to_possible_value()
+        .filter(|_| algos.contains(&HashAlgo::Crc16))
+    {
+        header.push_str(&format!(" crc16={}", variant.get_name()));
+    }
+    if config.blake2b_len != BLAKE2B_MAX_LEN && algos.contains(&HashAlgo::Blake2b) {
+        header.push_str(&format!(" blake2b={}", config.blake2b_len));
+    }
+    // Empreinte de la clé HMAC, pas la clé : le fichier d'état n'est pas un secret
+    if let Some(key) = config.hmac_key {
+        let mut hasher = Sha256::new();
+        hasher.update(b"zhashgen state hmac\0");
+        hasher.update(key);
+        let fingerprint: String = hasher.finalize()[..8]
+            .iter()
+            .map(|b| format!("{b:02x}"))
+            .collect();
+        header.push_str(&format!(" hmac={fingerprint}"));
+    }
+    header
+}
+
+// Digests du précédent passage si la taille et la date de modification sont inchangées
+fn unchanged_digests(
+    previous: &HashMap<String, StateEntry>,
+    rel: &Path,
+    path: &Path,
+) -> Option<Vec<FileDigest>> {
+    let entry = previous.get(&slash_path(rel))?;
+    let meta = fs::metadata(long_path(path)).ok()?;
+    let modified = meta.modified().ok()?;
+    (meta.len() == entry.size && mtime_nanos(modified) == Some(entry.modified)).then(|| {
+        entry
+            .digests
+            .iter()
+            .map(|bytes| FileDigest {
+                bytes: bytes.clone(),
+                size: entry.size,
+                modified: Some(modified),
+                changed: false,
+            })
+            .collect()
+    })
+}
+
+fn decode_hex(hex: &str) -> Option<Vec<u8>> {
+    if !hex.len().is_multiple_of(2) {
+        return None;
+    }
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok())
+        .collect()
+}
+
+// --------------------------------------------------------------------------------
+// VÉRIFICATION (--check)
+// --------------------------------------------------------------------------------
+
+#[derive(Copy, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "UPPERCASE")]
+enum CheckStatus {
+    Ok,
+    Failed,
+    Missing,
+}
+
+impl CheckStatus {
+    fn label(self) -> &'static str {
+        match self {
+            CheckStatus::Ok => "OK",
+            CheckStatus::Failed => "FAILED",
+            CheckStatus::Missing => "MISSING",
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct CheckEntry {
+    path: String,
+    status: CheckStatus,
+    expected: String,
+    actual: Option<String>,
+    error: Option<String>,
+}
+
+#[derive(Serialize)]
+struct CheckReport {
+    total: usize,
+    ok: usize,
+    failed: usize,
+    missing: usize,
+    files: Vec<CheckEntry>,
+}
+
+// Recalcule chaque entrée du manifeste et compare au digest enregistré.
+// Retourne `true` si tous les fichiers correspondent.
+fn run_check(args: &Args, manifest: &Path) -> io::Result<bool> {
+    let files = if manifest.is_dir() {
+        check_tree(args, manifest)?
+    } else {
+        check_manifest(args, manifest, &args.source)?
+    };
+    report_check(args, files)
+}
+
+// --check-url : le nom du manifeste (dernier segment de l'URL) sert comme en local à
+// reconnaître un .sfv ou l'algorithme d'après l'extension
+fn run_check_url(args: &Args, url: &str) -> io::Result<bool> {
+    let content = fetch_manifest(url, args.lang)?;
+    let name = url
+        .split(['?', '#'])
+        .next()
+        .and_then(|url| url.rsplit('/').next())
+        .unwrap_or_default();
+    let files = check_content(args, Path::new(name), &content, &args.source)?;
+    report_check(args, files)
+}
+
+// Corps d'une réponse 200 ; toute autre issue (statut, réseau, TLS) est une erreur
+fn fetch_manifest(url: &str, lang: Lang) -> io::Result<Vec<u8>> {
+    let response = match ureq::get(url).call() {
+        Ok(response) => response,
+        Err(ureq::Error::Status(code, response)) => {
+            let kind = match code {
+                404 | 410 => io::ErrorKind::NotFound,
+                401 | 403 => io::ErrorKind::PermissionDenied,
+                _ => io::ErrorKind::Other,
+            };
+            return Err(io::Error::new(
+                kind,
+                format!("{url} : HTTP {code} {}", response.status_text()),
+            ));
+        }
+        Err(e) => return Err(io::Error::other(format!("{url} : {e}"))),
+    };
+    if response.status() != 200 {
+        return Err(io::Error::other(format!(
+            "{url} : HTTP {} {} (200 {})",
+            response.status(),
+            response.status_text(),
+            Msg::Expected.text(lang)
+        )));
+    }
+    let mut content = Vec::new();
+    response.into_reader().read_to_end(&mut content)?;
+    Ok(content)
+}
+
+// Rapport commun à --check et --check-url ; `true` si tous les fichiers correspondent
+fn report_check(args: &Args, files: Vec<CheckEntry>) -> io::Result<bool> {
+    let count = |status| files.iter().filter(|f| f.status == status).count();
+    let report = CheckReport {
+        total: files.len(),
+        ok: count(CheckStatus::Ok),
+        failed: count(CheckStatus::Failed),
+        missing: count(CheckStatus::Missing),
+        files,
+    };
+    let all_ok = report.ok == report.total;
+
+    match args.check_format {
+        CheckFormat::Text => {
+            for entry in &report.files {
+                match &entry.error {
+                    Some(e) if entry.status == CheckStatus::Failed => {
+                        println!("{}: {} ({e})", entry.path, entry.status.label())
+                    }
+                    _ => println!("{}: {}", entry.path, entry.status.label()),
+                }
+            }
+            println!(
+                "{} files, {} OK, {} FAILED, {} MISSING",
+                report.total, report.ok, report.failed, report.missing
+            );
+        }
+        CheckFormat::Json => {
+            let stdout = io::stdout();
+            let mut out = stdout.lock();
+            serde_json::to_writer_pretty(&mut out, &report)?;
+            writeln!(out)?;
+        }
+    }
+
+    Ok(all_ok)
+}
+
+// Arborescence --per-dir : chaque manifeste --name est vérifié par rapport à son
+// répertoire ; les chemins du rapport sont relatifs à `dir`
+fn check_tree(args: &Args, dir: &Path) -> io::Result<Vec<CheckEntry>> {
+    let manifests: Vec<PathBuf> = WalkDir::new(long_path(dir))
+        .sort_by_file_name()
+        .into_iter()
+        .filter_map(|e| e.ok())
+        .filter(|e| e.file_type().is_file() && e.file_name() == args.name.as_str())
+        .map(|e| e.into_path())
+        .collect();
+    if manifests.is_empty() {
+        return Err(io::Error::new(
+            io::ErrorKind::NotFound,
+            format!(
+                "{} {} {} {}",
+                Msg::NoManifest.text(args.lang),
+                args.name,
+                Msg::Under.text(args.lang),
+                dir.display()
+            ),
+        ));
+    }
+    let walk_root = long_path(dir);
+    let mut files = Vec::new();
+    for manifest in &manifests {
+        let source = manifest.parent().unwrap_or(dir);
+        let prefix = slash_path(source.strip_prefix(&walk_root).unwrap_or(source));
+        for mut entry in check_manifest(args, manifest, source)? {
+            if !prefix.is_empty() {
+                entry.path = format!("{prefix}/{}", entry.path);
+            }
+            files.push(entry);
+        }
+    }
+    Ok(files)
+}
+
+// Entrées d'un manifeste, résolues relativement à `source`
+fn check_manifest(args: &Args, manifest: &Path, source: &Path) -> io::Result<Vec<CheckEntry>> {
+    // Lu en octets : les chemins non UTF-8 (Unix) sont retrouvés tels qu'écrits
+    let content = fs::read(manifest)?;
+    check_content(args, manifest, &content, source)
+}
+
+// `manifest` ne sert qu'à son nom (extension .sfv ou d'algorithme) et aux messages
+fn check_content(
+    args: &Args,
+    manifest: &Path,
+    content: &[u8],
+    source: &Path,
+) -> io::Result<Vec<CheckEntry>> {
+    verify_manifest_checksum(manifest, content, args.lang)?;
+    // Un .sfv est toujours du CRC32 en hexadécimal, quels que soient --algo et --encoding
+    let sfv = is_sfv(manifest);
+    let encoding = if sfv { Encoding::Hex } else { args.encoding };
+    // Un manifeste --decompress se vérifie sur le contenu décompressé
+    let decompress = args.decompress || has_marker(content, DECOMPRESS_MARKER);
+    // Un manifeste --head se vérifie sur les mêmes premiers octets
+    let head = args.head.or_else(|| manifest_head(content));
+    // ... et un manifeste --include-metadata-in-hash avec les attributs
+    let metadata = args.include_metadata_in_hash || has_marker(content, METADATA_MARKER);
+    let parse = if sfv {
+        parse_sfv_line
+    } else {
+        parse_manifest_line
+    };
+    let lines: Vec<_> = content.split(|&b| b == b'\n').filter_map(parse).collect();
+    // Digests raccourcis (--truncate) : leur longueur ne désigne plus l'algorithme
+    let truncated = args.truncate.is_some() && !sfv;
+    // Les lignes étiquetées (SFV, BSD) désignent leur algorithme, les autres le déduisent
+    let algos = lines
+        .iter()
+        .map(|&(tagged, digest, _)| match tagged {
+            Some(algo) => Ok(algo),
+            None if truncated => Ok(args.primary_algo()),
+            None => detect_algo(
+                manifest,
+                expected_hex_len(digest, encoding, args.lang)?,
+                args,
+            ),
+        })
+        .collect::<io::Result<Vec<_>>>()?;
+    if HMAC_KEY.get().is_some() {
+        check_hmac_algos(&algos, args)?;
+    } else if has_marker(content, HMAC_MARKER) {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            Msg::HmacManifest.text(args.lang),
+        ));
+    }
+    let entries: Vec<_> = lines
+        .into_iter()
+        .map(|(_, digest, path)| (digest, path))
+        .collect();
+
+    let progress = Progress::new(
+        entries.len() as u64,
+        entries.len(),
+        ProgressUnit::Files,
+        args,
+    );
+    let files: Vec<_> = entries
+        .par_iter()
+        .zip(algos.par_iter())
+        .map(|(&(expected, recorded), &algo)| {
+            let path = resolve_manifest_path(source, recorded);
+            let config = HashConfig {
+                algo,
+                decompress,
+                head,
+                metadata,
+                ..args.hash_config()
+            };
+            let render = |d: FileDigest| {
+                if sfv {
+                    d.to_hex()
+                } else {
+                    encode_digest(&d, args)
+                }
+            };
+            let matches = |actual: &str| match actual.get(..expected.len()) {
+                Some(prefix) if truncated && !expected.is_empty() => {
+                    digests_match(prefix, expected, encoding)
+                }
+                _ => digests_match(actual, expected, encoding),
+            };
+            let digest = guard_panic(&path, args, || match symlink_entry(&path, args) {
+                Some(_) => hash_symlink_target(&path, &[algo], &config).map(|mut d| d.remove(0)),
+                None => hash_file(&path, &config),
+            });
+            let (status, actual, error) = match digest.map(render) {
+                Ok(digest) if matches(&digest) => (CheckStatus::Ok, Some(digest), None),
+                Ok(digest) => (CheckStatus::Failed, Some(digest), None),
+                Err(e) if e.kind() == io::ErrorKind::NotFound => {
+                    (CheckStatus::Missing, None, Some(e.to_string()))
+                }
+                Err(e) => (CheckStatus::Failed, None, Some(e.to_string())),
+            };
+            let path_text = String::from_utf8_lossy(recorded).into_owned();
+            let bytes = fs::metadata(long_path(&path)).map_or(0, |meta| meta.len());
+            progress.file_done(&path_text, bytes);
+            CheckEntry {
+                path: path_text,
+                status,
+                expected: expected.to_string(),
+                actual,
+                error,
+            }
+        })
+        .collect();
+    progress.finish();
+    Ok(files)
+}
+
+// Pied de page --manifest-checksum : s'il est présent, le contenu qui le précède doit
+// correspondre, sinon aucune entrée n'est vérifiée
+fn verify_manifest_checksum(manifest: &Path, content: &[u8], lang: Lang) -> io::Result<()> {
+    // Dernier enregistrement non vide (séparé par \n, ou NUL avec --print0)
+    let body = content
+        .strip_suffix(b"\n")
+        .or_else(|| content.strip_suffix(b"\0"))
+        .unwrap_or(content);
+    let start = body
+        .iter()
+        .rposition(|&b| b == b'\n' || b == b'\0')
+        .map_or(0, |i| i + 1);
+    let footer = body[start..].strip_suffix(b"\r").unwrap_or(&body[start..]);
+    let Some(expected) = [b"# ".as_slice(), b"; "]
+        .iter()
+        .find_map(|comment| footer.strip_prefix(*comment))
+        .and_then(|rest| rest.strip_prefix(MANIFEST_CHECKSUM_TAG.as_bytes()))
+    else {
+        return Ok(());
+    };
+    let expected = String::from_utf8_lossy(expected).trim().to_string();
+    let actual: String = Sha256::digest(&content[..start])
+        .iter()
+        .map(|b| format!("{b:02x}"))
+        .collect();
+    if !actual.eq_ignore_ascii_case(&expected) {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!(
+                "{} {} ({MANIFEST_CHECKSUM_TAG} {expected} {}, {actual} {})",
+                Msg::TamperedManifest.text(lang),
+                manifest.display(),
+                Msg::Expected.text(lang),
+                Msg::Computed.text(lang)
+            ),
+        ));
+    }
+    info!("{MANIFEST_CHECKSUM_TAG} {}", Msg::FooterVerified.text(lang));
+    Ok(())
+}
+
+// Longueur hexadécimale équivalente du digest enregistré, pour la détection d'algorithme
+fn expected_hex_len(digest: &str, encoding: Encoding, lang: Lang) -> io::Result<usize> {
+    match encoding {
+        Encoding::Hex => Ok(digest.len()),
+        Encoding::Base64 => BASE64
+            .decode(digest)
+            .map(|bytes| bytes.len() * 2)
+            .map_err(|e| {
+                io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("{} '{digest}' ({e})", Msg::InvalidBase64.text(lang)),
+                )
+            }),
+    }
+}
+
+// L'hexadécimal se compare sans tenir compte de la casse ; le base64 y est sensible
+fn digests_match(actual: &str, expected: &str, encoding: Encoding) -> bool {
+    match encoding {
+        Encoding::Hex => actual.eq_ignore_ascii_case(expected),
+        Encoding::Base64 => actual == expected,
+    }
+}
+
+// Déduit l'algorithme de la longueur du digest. En cas d'ambiguïté (ex. 64 caractères
+// pour SHA256/BLAKE3), on se fie à l'extension du manifeste (CRC.sha256), puis à --algo.
+fn detect_algo(manifest: &Path, hex_len: usize, args: &Args) -> io::Result<HashAlgo> {
+    let fallback = args.primary_algo();
+    let candidates: Vec<HashAlgo> = HashAlgo::value_variants()
+        .iter()
+        .copied()
+        .filter(|&a| args.hex_len(a) == hex_len)
+        .collect();
+
+    match candidates.as_slice() {
+        [] => Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!(
+                "{} {hex_len} {} ({})",
+                Msg::NoAlgoForLength.text(args.lang),
+                Msg::Characters.text(args.lang),
+                Msg::ShortenedManifest.text(args.lang)
+            ),
+        )),
+        [only] => Ok(*only),
+        _ => algo_from_extension(manifest)
+            .filter(|a| candidates.contains(a))
+            .or_else(|| candidates.contains(&fallback).then_some(fallback))
+            .ok_or_else(|| {
+                let names: Vec<_> = candidates.iter().map(|a| a.extension()).collect();
+                io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!(
+                        "{} {hex_len} {} ({}), {}",
+                        Msg::AmbiguousLength.text(args.lang),
+                        Msg::Characters.text(args.lang),
+                        names.join(", "),
+                        Msg::SpecifyAlgo.text(args.lang)
+                    ),
+                )
+            }),
+    }
+}
+
+// Reconnaît l'extension des manifestes (CRC.sha256) ou le nom clap de l'algo (x.xxh3)
+fn algo_from_extension(manifest: &Path) -> Option<HashAlgo> {
+    let ext = manifest.extension()?.to_str()?.to_ascii_lowercase();
+    HashAlgo::value_variants().iter().copied().find(|a| {
+        a.extension() == ext || a.to_possible_value().is_some_and(|v| v.get_name() == ext)
+    })
+}
+
+// Commentaire d'en-tête (# ou ;) commençant par `marker`
+fn has_marker(content: &[u8], marker: &str) -> bool {
+    content.split(|&b| b == b'\n').any(|line| {
+        (line.starts_with(b"# ") || line.starts_with(b"; "))
+            && line[2..].starts_with(marker.as_bytes())
+    })
+}
+
+// Octets hachés par fichier d'après l'en-tête « # zhashgen: head N » d'un manifeste --head
+fn manifest_head(content: &[u8]) -> Option<u64> {
+    content.split(|&b| b == b'\n').find_map(|line| {
+        let comment = line
+            .strip_prefix(b"# ")
+            .or_else(|| line.strip_prefix(b"; "))?;
+        let rest = comment.strip_prefix(HEAD_MARKER.as_bytes())?;
+        let rest = std::str::from_utf8(rest).ok()?;
+        rest.split_whitespace().next()?.parse().ok()
+    })
+}
+
+fn is_sfv(manifest: &Path) -> bool {
+    manifest
+        .extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("sfv"))
+}
+
+// Ligne SFV « chemin CRC32 » : le digest suit le dernier espace, les commentaires commencent par ;
+fn parse_sfv_line(line: &[u8]) -> Option<(Option<HashAlgo>, &str, &[u8])> {
+    let line = line.strip_suffix(b"\r").unwrap_or(line);
+    if line.is_empty() || line.starts_with(b";") {
+        return None;
+    }
+    let space = line.iter().rposition(|&b| b == b' ')?;
+    let digest = std::str::from_utf8(&line[space + 1..]).ok()?;
+    Some((Some(HashAlgo::Crc32), digest, &line[..space]))
+}
+
+// Découpe une ligne « digest *chemin » (ou « digest  chemin » façon coreutils), ou une
+// ligne étiquetée « ALGO (chemin) = digest ». Les lignes vides, commentaires et lignes
+// [ERROR] sont ignorées, tout comme les colonnes --with-meta (après la première tabulation).
+fn parse_manifest_line(line: &[u8]) -> Option<(Option<HashAlgo>, &str, &[u8])> {
+    let line = line.strip_suffix(b"\r").unwrap_or(line);
+    if line.is_empty() || line.starts_with(b"#") || line.starts_with(b"[ERROR]") {
+        return None;
+    }
+    if let Some((algo, digest, path)) = parse_bsd_line(line) {
+        return Some((Some(algo), digest, path));
+    }
+    let space = line.iter().position(|&b| b == b' ')?;
+    let digest = std::str::from_utf8(&line[..space]).ok()?;
+    let rest = &line[space + 1..];
+    let path = rest
+        .strip_prefix(b"*")
+        .or_else(|| rest.strip_prefix(b" "))
+        .unwrap_or(rest);
+    let path = path.split(|&b| b == b'\t').next().unwrap_or(path);
+    Some((None, digest, path))
+}
+
+// « SHA256 (chemin) = digest » : l'étiquette doit être un algorithme connu ; le chemin
+// s'arrête à la dernière occurrence de « ) = », il peut donc contenir des parenthèses
+fn parse_bsd_line(line: &[u8]) -> Option<(HashAlgo, &str, &[u8])> {
+    let open = line.windows(2).position(|w| w == b" (")?;
+    let algo = HashAlgo::from_tag(std::str::from_utf8(&line[..open]).ok()?)?;
+    let rest = &line[open + 2..];
+    let close = rest.windows(4).rposition(|w| w == b") = ")?;
+    let digest = &rest[close + 4..];
+    let digest = digest.split(|&b| b == b'\t').next().unwrap_or(digest);
+    Some((algo, std::str::from_utf8(digest).ok()?, &rest[..close]))
+}
+
+// Les manifestes historiques enregistrent `..\chemin` avec des séparateurs Windows :
+// on normalise et on résout le chemin relativement à --source.
+fn resolve_manifest_path(source: &Path, recorded: &[u8]) -> PathBuf {
+    let normalized: Vec<u8> = recorded
+        .iter()
+        .map(|&b| if b == b'\\' { b'/' } else { b })
+        .collect();
+    let rel = normalized.strip_prefix(b"../").unwrap_or(&normalized);
+    source.join(path_from_bytes(rel))
+}
+
+#[cfg(unix)]
+fn path_from_bytes(bytes: &[u8]) -> PathBuf {
+    use std::os::unix::ffi::OsStrExt;
+    PathBuf::from(std::ffi::OsStr::from_bytes(bytes))
+}
+
+#[cfg(not(unix))]
+fn path_from_bytes(bytes: &[u8]) -> PathBuf {
+    PathBuf::from(String::from_utf8_lossy(bytes).into_owned())
+}
+
+// --------------------------------------------------------------------------------
+// COMPARAISON (--compare)
+// --------------------------------------------------------------------------------
+
+#[derive(Copy, Clone)]
+enum Difference {
+    // Présent uniquement dans OTHER_DIR
+    Added,
+    // Présent uniquement dans --source
+    Removed,
+    // Présent des deux côtés avec des digests différents
+    Changed,
+}
+
+impl Difference {
+    fn label(self) -> &'static str {
+        match self {
+            Difference::Added => "ADDED",
+            Difference::Removed => "REMOVED",
+            Difference::Changed => "CHANGED",
+        }
+    }
+}
+
+// Hache les deux arbres avec les mêmes filtres et compare les digests par chemin relatif.
+// Retourne `true` si les arbres sont identiques.
+fn run_compare(args: &Args, other: &Path) -> io::Result<bool> {
+    let hash_tree = |root: &Path| -> io::Result<HashMap<String, FileRecord>> {
+        let mut files = scan_source(args, root, &[], None)?;
+        retain_size_range(&mut files, args);
+        let mut records = HashMap::new();
+        hash_files(&files, root, args, &HashMap::new(), |record| {
+            records.insert(slash_path(&record.rel), record);
+            Ok(())
+        })?;
+        Ok(records)
+    };
+    let source = hash_tree(&args.source)?;
+    let other = hash_tree(other)?;
+
+    let mut paths: Vec<&String> = source.keys().chain(other.keys()).collect();
+    paths.sort();
+    paths.dedup();
+
+    let (mut added, mut removed, mut changed, mut errors) = (0, 0, 0, 0);
+    for path in paths {
+        let difference = match (source.get(path), other.get(path)) {
+            (Some(_), None) => Difference::Removed,
+            (None, Some(_)) => Difference::Added,
+            (Some(left), Some(right)) => match (&left.result, &right.result) {
+                (Ok(a), Ok(b)) if a.bytes == b.bytes => continue,
+                (Ok(_), Ok(_)) => Difference::Changed,
+                (Err(e), _) | (_, Err(e)) => {
+                    println!("{path}: ERROR ({e})");
+                    errors += 1;
+                    continue;
+                }
+            },
+            (None, None) => unreachable!(),
+        };
+        match difference {
+            Difference::Added => added += 1,
+            Difference::Removed => removed += 1,
+            Difference::Changed => changed += 1,
+        }
+        println!("{path}: {}", difference.label());
+    }
+    println!("{added} added, {removed} removed, {changed} changed, {errors} errors");
+
+    Ok(added + removed + changed + errors == 0)
+}
+
+// --------------------------------------------------------------------------------
+// SURVEILLANCE (--watch)
+// --------------------------------------------------------------------------------
+
+// Fin d'une rafale : aucun nouvel événement pendant ce délai
+const WATCH_DEBOUNCE: Duration = Duration::from_millis(500);
+// Attente maximale entre deux lectures de Ctrl-C
+const WATCH_POLL: Duration = Duration::from_millis(200);
+
+// Destination et exclusions du manifeste surveillé
+struct Watched<'a> {
+    manifest: &'a Path,
+    state_file: Option<&'a Path>,
+    excluded: &'a [PathBuf],
+    output_dir: Option<&'a Path>,
+}
+
+// Génération initiale puis, à chaque rafale d'événements, nouveau parcours (filtres
+// compris) : seuls les fichiers signalés ou nouveaux sont re-hachés, les disparus retirés
+fn run_watch(
+    args: &Args,
+    watched: &Watched,
+    files: Vec<PathBuf>,
+    previous: &HashMap<String, StateEntry>,
+) -> io::Result<()> {
+    // Surveillance posée avant le premier passage : aucune modification n'est manquée
+    let (sender, events) = mpsc::channel();
+    let mut watcher = notify::recommended_watcher(sender).map_err(io::Error::other)?;
+    watcher
+        .watch(&args.source, RecursiveMode::Recursive)
+        .map_err(io::Error::other)?;
+    let canonical_source = args.source.canonicalize()?;
+
+    // Entrées du manifeste par chemin relatif, dans l'ordre d'écriture
+    let mut records = BTreeMap::new();
+    let mut insert = |record: FileRecord| {
+        records.insert(slash_path(&record.rel), record);
+        Ok(())
+    };
+    hash_files(&files, &args.source, args, previous, &mut insert)?;
+    write_watched(watched, &records, args)?;
+    eprintln!(
+        "{} {}",
+        Msg::Watching.text(args.lang),
+        args.source.display()
+    );
+
+    while !INTERRUPTED.load(Ordering::SeqCst) {
+        let mut changed = HashSet::new();
+        let mut note = |event: notify::Result<Event>| match event {
+            Ok(event) if matches!(event.kind, EventKind::Access(_)) => {}
+            Ok(event) => changed.extend(event.paths.iter().filter_map(|path| {
+                let rel = path
+                    .strip_prefix(&args.source)
+                    .or_else(|_| path.strip_prefix(&canonical_source))
+                    .ok()?;
+                Some(slash_path(rel))
+            })),
+            Err(e) => error!("--watch : {e}"),
+        };
+        match events.recv_timeout(WATCH_POLL) {
+            Ok(event) => note(event),
+            Err(mpsc::RecvTimeoutError::Timeout) => continue,
+            Err(mpsc::RecvTimeoutError::Disconnected) => break,
+        }
+        while let Ok(event) = events.recv_timeout(WATCH_DEBOUNCE) {
+            note(event);
+        }
+
+        let (current, _) = collect_files(args, watched.excluded, watched.output_dir)?;
+        let rel_of = |path: &PathBuf| slash_path(path.strip_prefix(&args.source).unwrap_or(path));
+        let current_rels: HashSet<String> = current.iter().map(rel_of).collect();
+        let before = records.len();
+        records.retain(|rel, _| current_rels.contains(rel));
+        let removed = before - records.len();
+        let todo: Vec<PathBuf> = current
+            .into_iter()
+            .filter(|path| {
+                let rel = rel_of(path);
+                changed.contains(&rel) || !records.contains_key(&rel)
+            })
+            .collect();
+        // Événements hors sélection (manifeste lui-même, fichiers filtrés) : rien à faire
+        if todo.is_empty() && removed == 0 {
+            continue;
+        }
+
+        let mut insert = |record: FileRecord| {
+            records.insert(slash_path(&record.rel), record);
+            Ok(())
+        };
+        hash_files(&todo, &args.source, args, &HashMap::new(), &mut insert)?;
+        write_watched(watched, &records, args)?;
+        info!(
+            "--watch : {} {}, {removed} {}",
+            todo.len(),
+            Msg::Rehashed.text(args.lang),
+            Msg::Removed.text(args.lang)
+        );
+    }
+    Ok(())
+}
+
+// Réécrit le manifeste complet (et l'état de --update) à partir des entrées courantes
+fn write_watched(
+    watched: &Watched,
+    records: &BTreeMap<String, FileRecord>,
+    args: &Args,
+) -> io::Result<()> {
+    let out = Box::new(BufWriter::new(File::create(watched.manifest)?));
+    let mut writer = ManifestWriter::new(out, args)?;
+    let mut errors = ErrorLog::new(errors_path(args, Some(watched.manifest)), args)?;
+    for record in records.values() {
+        writer.write(record, &mut errors, args)?;
+    }
+    writer.finish(args)?;
+    errors.finish()?;
+    if let Some(path) = watched.state_file {
+        save_state(path, &args.algo, &args.hash_config(), records.values())?;
+    }
+    Ok(())
+}
+
+// --------------------------------------------------------------------------------
+// BENCHMARK (--bench)
+// --------------------------------------------------------------------------------
+
+// Chaque algorithme hache les mêmes données via le chemin streaming (hash_reader),
+// cache chaud : une passe de chauffe puis --bench-iterations passes mesurées.
+// Tableau trié du plus rapide au plus lent.
+fn run_bench(args: &Args) -> io::Result<()> {
+    let data = if args.source.is_file() {
+        None
+    } else {
+        Some(synthetic_data(args.bench_size))
+    };
+    let iterations = args.bench_iterations.max(1);
+    let run = |config: &HashConfig| -> io::Result<FileDigest> {
+        match &data {
+            Some(data) => hash_reader(data.as_slice(), config),
+            None => hash_reader(File::open(&args.source)?, config),
+        }
+    };
+
+    let mut rows = Vec::new();
+    for &algo in HashAlgo::value_variants() {
+        let config = HashConfig {
+            algo,
+            ..args.hash_config()
+        };
+        let size = run(&config)?.size;
+        let start = Instant::now();
+        for _ in 0..iterations {
+            run(&config)?;
+        }
+        let secs = start.elapsed().as_secs_f64() / iterations as f64;
+        rows.push((algo_name(algo), size as f64 / 1e6 / secs));
+    }
+    rows.sort_by(|a, b| b.1.total_cmp(&a.1));
+
+    let input = match &data {
+        Some(data) => format!("synthetic, {}", human_readable(data.len() as u64)),
+        None => args.source.display().to_string(),
+    };
+    println!("{input}, {iterations} iterations");
+    println!("{:<12} {:>12}", "algo", "MB/s");
+    for (name, throughput) in rows {
+        println!("{name:<12} {throughput:>12.1}");
+    }
+    Ok(())
+}
+
+// Octets pseudo-aléatoires déterministes (xorshift64), incompressibles pour les hachages
+fn synthetic_data(len: usize) -> Vec<u8> {
+    let mut state: u64 = 0x9E37_79B9_7F4A_7C15;
+    let mut data = Vec::with_capacity(len + 8);
+    while data.len() < len {
+        state ^= state << 13;
+        state ^= state >> 7;
+        state ^= state << 17;
+        data.extend_from_slice(&state.to_le_bytes());
+    }
+    data.truncate(len);
+    data
+}
+
+// --------------------------------------------------------------------------------
+// UTILS
+// --------------------------------------------------------------------------------
+
+// Barre sur stderr uniquement, pour ne jamais mélanger progression et sortie stdout
+// Barre en nombre de fichiers (--check) ou en octets, fichiers traités dans {msg}
+const COUNT_TEMPLATE: &str = "[{elapsed_precise}] {bar:40.cyan/blue} {pos}/{len} {msg}";
+const BYTES_TEMPLATE: &str = "[{elapsed_precise}] {bar:40.cyan/blue} {binary_bytes}/{binary_total_bytes} \
+     ({binary_bytes_per_sec}, ETA {eta}) {msg}";
+
+fn new_progress_bar(len: u64, template: &str, args: &Args) -> ProgressBar {
+    if args.no_progress || !io::stderr().is_terminal() {
+        return ProgressBar::hidden();
+    }
+    let pb = ProgressBar::with_draw_target(Some(len), ProgressDrawTarget::stderr());
+    pb.set_style(
+        ProgressStyle::with_template(template)
+            .unwrap()
+            .progress_chars("##-"),
+    );
+    pb
+}
+
+// Unité de la barre : octets (génération, avec ETA) ou fichiers (--check)
+#[derive(Copy, Clone, PartialEq, Eq)]
+enum ProgressUnit {
+    Bytes,
+    Files,
+}
+
+// Suivi de l'avancement, indépendant de son rendu (--progress-format)
+struct Progress {
+    sink: ProgressSink,
+    unit: ProgressUnit,
+    total: usize,
+    done: AtomicUsize,
+    label: &'static str,
+    eta: Option<WeightedEta>,
+}
+
+// ETA de la barre en octets, calculée sur un coût estimé plutôt que sur les octets seuls :
+// chaque fichier coûte son ouverture et sa lecture (PER_FILE_COST_NS), puis chaque octet
+// le temps de passer dans les algorithmes de --algo à leur débit nominal. Avec un
+// algorithme rapide, une fin de parcours faite de petits fichiers ne paraît plus immédiate.
+struct WeightedEta {
+    // Nanosecondes par octet, tous algorithmes confondus
+    per_byte: f64,
+    done: Arc<AtomicU64>,
+}
+
+const PER_FILE_COST_NS: u64 = 50_000;
+
+impl WeightedEta {
+    fn new(algos: &[HashAlgo]) -> Self {
+        let per_byte = algos
+            .iter()
+            .map(|algo| 1e9 / (algo.nominal_throughput() * 1024 * 1024) as f64)
+            .sum();
+        WeightedEta {
+            per_byte,
+            done: Arc::new(AtomicU64::new(0)),
+        }
+    }
+
+    fn cost(&self, files: usize, bytes: u64) -> u64 {
+        files as u64 * PER_FILE_COST_NS + (bytes as f64 * self.per_byte) as u64
+    }
+
+    // Clé {eta} du modèle : temps écoulé × coût restant / coût déjà fait
+    fn install(&self, pb: &ProgressBar, files: usize, bytes: u64) {
+        let total = self.cost(files, bytes);
+        let done = Arc::clone(&self.done);
+        let eta = move |state: &ProgressState, w: &mut dyn std::fmt::Write| {
+            let done = done.load(Ordering::Relaxed);
+            let eta = match done {
+                0 => Duration::ZERO,
+                _ => state
+                    .elapsed()
+                    .mul_f64(total.saturating_sub(done) as f64 / done as f64),
+            };
+            let _ = w.write_str(&format!("{:#}", HumanDuration(eta)));
+        };
+        pb.set_style(pb.style().with_key("eta", eta));
+    }
+}
+
+enum ProgressSink {
+    Bar(ProgressBar),
+    Json,
+    Hidden,
+}
+
+#[derive(Serialize)]
+struct ProgressEvent<'a> {
+    done: usize,
+    total: usize,
+    path: &'a str,
+    bytes: u64,
+}
+
+impl Progress {
+    // `len` : longueur de la barre dans l'unité choisie ; `total` : nombre de fichiers
+    fn new(len: u64, total: usize, unit: ProgressUnit, args: &Args) -> Self {
+        let sink = match args.progress_format {
+            _ if args.no_progress => ProgressSink::Hidden,
+            ProgressFormat::Json => ProgressSink::Json,
+            ProgressFormat::Bar => {
+                let template = match unit {
+                    ProgressUnit::Bytes => BYTES_TEMPLATE,
+                    ProgressUnit::Files => COUNT_TEMPLATE,
+                };
+                ProgressSink::Bar(new_progress_bar(len, template, args))
+            }
+        };
+        let mut progress = Progress {
+            sink,
+            unit,
+            total,
+            done: AtomicUsize::new(0),
+            label: Msg::Files.text(args.lang),
+            eta: None,
+        };
+        if let (ProgressSink::Bar(pb), ProgressUnit::Bytes) = (&progress.sink, unit) {
+            pb.set_message(format!("0/{total} {}", progress.label));
+            let eta = WeightedEta::new(&args.algo);
+            eta.install(pb, total, len);
+            progress.eta = Some(eta);
+        }
+        progress
+    }
+
+    // Appelé depuis les threads de hachage, dans l'ordre d'achèvement
+    fn file_done(&self, path: &str, bytes: u64) {
+        let done = self.done.fetch_add(1, Ordering::Relaxed) + 1;
+        match &self.sink {
+            ProgressSink::Bar(pb) if self.unit == ProgressUnit::Bytes => {
+                if let Some(eta) = &self.eta {
+                    eta.done.fetch_add(eta.cost(1, bytes), Ordering::Relaxed);
+                }
+                pb.set_message(format!("{done}/{} {}", self.total, self.label));
+                pb.inc(bytes);
+            }
+            ProgressSink::Bar(pb) => pb.inc(1),
+            ProgressSink::Json => {
+                let event = ProgressEvent {
+                    done,
+                    total: self.total,
+                    path,
+                    bytes,
+                };
+                // Une ligne par appel : eprintln! verrouille stderr le temps de l'écriture
+                if let Ok(line) = serde_json::to_string(&event) {
+                    eprintln!("{line}");
+                }
+            }
+            ProgressSink::Hidden => {}
+        }
+    }
+
+    // La barre en octets reste affichée à la fin de la génération, celle de --check s'efface
+    fn finish(&self) {
+        match &self.sink {
+            ProgressSink::Bar(pb) if self.unit == ProgressUnit::Bytes => pb.finish(),
+            ProgressSink::Bar(pb) => pb.finish_and_clear(),
+            ProgressSink::Json | ProgressSink::Hidden => {}
+        }
+    }
+}
+
+// Menu interactif : nom, précision éventuelle, algorithme et nom du manifeste
+const INTERACTIVE_ALGOS: [(&str, Option<Msg>, HashAlgo, &str); 21] = [
+    ("CRC32", None, HashAlgo::Crc32, "CRC.crc32"),
+    (
+        "CRC32C",
+        Some(Msg::NoteCastagnoli),
+        HashAlgo::Crc32c,
+        "CRC.crc32c",
+    ),
+    (
+        "City128",
+        Some(Msg::NoteCityMmap),
+        HashAlgo::City128,
+        "CRC.city128",
+    ),
+    (
+        "XXH3",
+        Some(Msg::NoteDefault),
+        HashAlgo::Xxh3,
+        "CRC.xxhash3",
+    ),
+    ("SHA256", None, HashAlgo::Sha256, "CRC.sha256"),
+    ("SHA512", None, HashAlgo::Sha512, "CRC.sha512"),
+    ("Blake2b (512-bit)", None, HashAlgo::Blake2b, "CRC.blake2b"),
+    ("Blake2s (256-bit)", None, HashAlgo::Blake2s, "CRC.blake2s"),
+    ("BLAKE3 (256-bit)", None, HashAlgo::Blake3, "CRC.blake3"),
+    ("MD5", Some(Msg::NoteMd5), HashAlgo::Md5, "CRC.md5"),
+    ("SHA1", None, HashAlgo::Sha1, "CRC.sha1"),
+    ("SHA3-256", None, HashAlgo::Sha3_256, "CRC.sha3-256"),
+    ("SHA3-512", None, HashAlgo::Sha3_512, "CRC.sha3-512"),
+    (
+        "XXH3 (128-bit)",
+        None,
+        HashAlgo::Xxh3_128,
+        "CRC.xxhash3-128",
+    ),
+    ("CRC64 (ECMA-182, xz)", None, HashAlgo::Crc64, "CRC.crc64"),
+    ("Adler-32 (zlib)", None, HashAlgo::Adler32, "CRC.adler32"),
+    (
+        "FNV-1a (64-bit)",
+        Some(Msg::NoteNoCollisionResistance),
+        HashAlgo::Fnv1a,
+        "CRC.fnv1a",
+    ),
+    (
+        "MurmurHash3 (128-bit)",
+        Some(Msg::NoteNoCollisionResistance),
+        HashAlgo::Murmur3,
+        "CRC.murmur3",
+    ),
+    ("CRC-16/CCITT", None, HashAlgo::Crc16, "CRC.crc16"),
+    ("SHA384", None, HashAlgo::Sha384, "CRC.sha384"),
+    ("SHA-512/256", None, HashAlgo::Sha512_256, "CRC.sha512-256"),
+];
+const INTERACTIVE_DEFAULT: usize = 4;
+
+fn get_interactive_args() -> io::Result<Args> {
+    // Valeurs par défaut de la ligne de commande, puis réponses de l'utilisateur
+    let mut args = Args::parse_from(["zhashgen"]);
+    args.output_dir = PathBuf::from("./NewCrc");
+
+    let lang = args.lang;
+    let menu_label = |(name, note, _, _): &(&str, Option<Msg>, HashAlgo, &str)| match note {
+        Some(note) => format!("{name} ({})", note.text(lang)),
+        None => name.to_string(),
+    };
+
+    println!("=== NewCrc gen v2 ===");
+    args.source = PathBuf::from(prompt(
+        Msg::SourceDir.text(lang),
+        &args.source.display().to_string(),
+    )?);
+
+    println!("{}", Msg::AlgoChoice.text(lang));
+    for (i, entry) in INTERACTIVE_ALGOS.iter().enumerate() {
+        println!("{:>3}. {}", i + 1, menu_label(entry));
+    }
+    let choice = prompt(
+        &format!(
+            "{} (1-{})",
+            Msg::YourChoice.text(lang),
+            INTERACTIVE_ALGOS.len()
+        ),
+        &INTERACTIVE_DEFAULT.to_string(),
+    )?;
+    let index = choice
+        .parse::<usize>()
+        .ok()
+        .filter(|n| (1..=INTERACTIVE_ALGOS.len()).contains(n))
+        .unwrap_or(INTERACTIVE_DEFAULT);
+    let entry = &INTERACTIVE_ALGOS[index - 1];
+    args.algo = vec![entry.2];
+    args.name = entry.3.to_string();
+
+    let threads = prompt(Msg::ThreadCount.text(lang), &args.threads.to_string())?;
+    args.threads = match threads.parse() {
+        Ok(n) if n > 0 => n,
+        _ => {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("{} : '{threads}'", Msg::InvalidThreads.text(lang)),
+            ));
+        }
+    };
+    args.output_dir = PathBuf::from(prompt(
+        Msg::OutputDir.text(lang),
+        &args.output_dir.display().to_string(),
+    )?);
+
+    let line = |msg: Msg, value: String| println!("  {:<11}: {}", msg.text(lang), value);
+    println!("\n{}", Msg::Configuration.text(lang));
+    line(Msg::Source, args.source.display().to_string());
+    line(Msg::Algorithm, menu_label(entry));
+    line(Msg::Threads, args.threads.to_string());
+    line(
+        Msg::Manifest,
+        args.output_dir.join(&args.name).display().to_string(),
+    );
+    Ok(args)
+}
+
+// Question avec valeur par défaut entre crochets, retenue si la réponse est vide
+fn prompt(question: &str, default: &str) -> io::Result<String> {
+    print!("{question} [{default}] : ");
+    io::stdout().flush()?;
+    let mut answer = String::new();
+    io::stdin().read_line(&mut answer)?;
+    let answer = answer.trim();
+    Ok(if answer.is_empty() { default } else { answer }.to_string())
+}
+
+// Taille lisible : "4096", "64KiB", "10MB", "1.5GiB"... Suffixes décimaux (KB, MB, GB, TB)
+// en puissances de 1000, binaires (KiB, MiB, K, M...) en puissances de 1024.
+fn parse_size(input: &str) -> Result<u64, String> {
+    let lang = current_lang();
+    let input = input.trim();
+    let split = input
+        .find(|c: char| !c.is_ascii_digit() && c != '.')
+        .unwrap_or(input.len());
+    let (number, unit) = input.split_at(split);
+    let number: f64 = number
+        .parse()
+        .map_err(|_| format!("{} '{input}'", Msg::InvalidSize.text(lang)))?;
+    let multiplier: u64 = match unit.trim().to_ascii_lowercase().as_str() {
+        "" | "b" => 1,
+        "kb" => 1000,
+        "mb" => 1000_u64.pow(2),
+        "gb" => 1000_u64.pow(3),
+        "tb" => 1000_u64.pow(4),
+        "k" | "kib" => 1 << 10,
+        "m" | "mib" => 1 << 20,
+        "g" | "gib" => 1 << 30,
+        "t" | "tib" => 1 << 40,
+        other => return Err(format!("{} '{other}'", Msg::UnknownSizeUnit.text(lang))),
+    };
+    Ok((number * multiplier as f64) as u64)
+}
+
+// Paramètre CRC32 en hexadécimal (préfixe 0x facultatif, au plus 8 chiffres)
+fn parse_crc32_hex(input: &str) -> Result<u32, String> {
+    let digits = input.trim();
+    let digits = digits
+        .strip_prefix("0x")
+        .or_else(|| digits.strip_prefix("0X"))
+        .unwrap_or(digits);
+    if digits.is_empty() || digits.len() > 8 || !digits.bytes().all(|b| b.is_ascii_hexdigit()) {
+        let lang = current_lang();
+        return Err(format!(
+            "{} '{input}' ({})",
+            Msg::InvalidCrc32Value.text(lang),
+            Msg::HexDigits.text(lang)
+        ));
+    }
+    u32::from_str_radix(digits, 16).map_err(|e| e.to_string())
+}
+
+fn parse_blake2b_length(input: &str) -> Result<usize, String> {
+    match input.trim().parse() {
+        Ok(len @ 1..=BLAKE2B_MAX_LEN) => Ok(len),
+        _ => {
+            let lang = current_lang();
+            Err(format!(
+                "{} '{input}' (1 {} {BLAKE2B_MAX_LEN} {})",
+                Msg::InvalidBlake2bLength.text(lang),
+                Msg::To.text(lang),
+                Msg::Bytes.text(lang)
+            ))
+        }
+    }
+}
+
+// --buffer-size : taille lisible, strictement positive
+fn parse_buffer_size(input: &str) -> Result<usize, String> {
+    match parse_size(input)? {
+        0 => Err(Msg::ZeroBuffer.text(current_lang()).to_string()),
+        size => usize::try_from(size)
+            .map_err(|_| format!("{} '{input}'", Msg::BufferTooLarge.text(current_lang()))),
+    }
+}
+
+fn human_readable(num_bytes: u64) -> String {
+    let units = ["B", "KiB", "MiB", "GiB", "TiB", "PiB"];
     let mut i = 0;
     let mut n = num_bytes as f64;
     while n >= 1024.0 && i < units.len() - 1 {
@@ -379,3 +4475,308 @@ fn human_readable(num_bytes: u64) -> String {
     }
     format!("{:.2} {}", n, units[i])
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ALGOS: [HashAlgo; 2] = [HashAlgo::Sha256, HashAlgo::Crc32];
+
+    fn record(rel: &str, size: u64, bytes: Vec<u8>, extra: Vec<u8>) -> FileRecord {
+        let modified = Some(UNIX_EPOCH + Duration::from_nanos(1_700_000_000_123_456_789));
+        let digest = |bytes| FileDigest {
+            bytes,
+            size,
+            modified,
+            changed: false,
+        };
+        FileRecord {
+            path: PathBuf::from(rel),
+            rel: PathBuf::from(rel),
+            result: Ok(digest(bytes)),
+            extra: vec![digest(extra)],
+            elapsed: Duration::ZERO,
+            link: None,
+        }
+    }
+
+    // Ligne découpée, algorithme réduit à son étiquette (HashAlgo n'implémente pas Debug)
+    type Parsed<'a> = Option<(Option<&'static str>, &'a str, &'a [u8])>;
+
+    fn tagged<'a>(parsed: Option<(Option<HashAlgo>, &'a str, &'a [u8])>) -> Parsed<'a> {
+        parsed.map(|(algo, digest, path)| (algo.map(HashAlgo::tag), digest, path))
+    }
+
+    #[test]
+    fn parse_sfv_lines() {
+        let cases: [(&[u8], Parsed); 7] = [
+            (
+                b"file.bin 1A2B3C4D",
+                Some((Some("CRC32"), "1A2B3C4D", b"file.bin")),
+            ),
+            (
+                b"file.bin 1A2B3C4D\r",
+                Some((Some("CRC32"), "1A2B3C4D", b"file.bin")),
+            ),
+            // Le digest suit le dernier espace : le chemin peut en contenir
+            (
+                b"dossier/mon fichier.bin 00000000",
+                Some((Some("CRC32"), "00000000", b"dossier/mon fichier.bin")),
+            ),
+            (b"; Generated by zhashgen", None),
+            (b";file.bin 1A2B3C4D", None),
+            (b"", None),
+            (b"sans-digest", None),
+        ];
+        for (line, expected) in cases {
+            assert_eq!(tagged(parse_sfv_line(line)), expected, "{line:?}");
+        }
+    }
+
+    #[test]
+    fn parse_bsd_lines() {
+        let cases: [(&[u8], Parsed); 6] = [
+            (
+                b"SHA256 (a.txt) = abcd",
+                Some((Some("SHA256"), "abcd", b"a.txt")),
+            ),
+            // Étiquette insensible à la casse, parenthèses permises dans le chemin
+            (
+                b"sha256 (f (1).txt) = abcd",
+                Some((Some("SHA256"), "abcd", b"f (1).txt")),
+            ),
+            (
+                b"XXH3-128 (a) = b) = 00ff",
+                Some((Some("XXH3-128"), "00ff", b"a) = b")),
+            ),
+            // Colonnes --with-meta après la tabulation
+            (
+                b"MD5 (a.txt) = abcd\t12\t2024",
+                Some((Some("MD5"), "abcd", b"a.txt")),
+            ),
+            (b"UNKNOWN (a.txt) = abcd", None),
+            (b"SHA256 a.txt = abcd", None),
+        ];
+        for (line, expected) in cases {
+            let parsed =
+                parse_bsd_line(line).map(|(algo, digest, path)| (Some(algo), digest, path));
+            assert_eq!(tagged(parsed), expected, "{line:?}");
+        }
+    }
+
+    #[test]
+    fn parse_manifest_lines() {
+        let cases: [(&[u8], Parsed); 10] = [
+            (b"abcd *a.txt", Some((None, "abcd", b"a.txt"))),
+            (b"abcd  a.txt", Some((None, "abcd", b"a.txt"))),
+            (b"abcd a.txt", Some((None, "abcd", b"a.txt"))),
+            (
+                b"abcd *mon fichier.txt\r",
+                Some((None, "abcd", b"mon fichier.txt")),
+            ),
+            (b"abcd *a.txt\t12\t2024", Some((None, "abcd", b"a.txt"))),
+            (
+                b"SHA1 (a.txt) = abcd",
+                Some((Some("SHA1"), "abcd", b"a.txt")),
+            ),
+            (b"# zhashgen", None),
+            (b"[ERROR] a.txt: Permission denied", None),
+            (b"", None),
+            (b"abcd", None),
+        ];
+        for (line, expected) in cases {
+            assert_eq!(tagged(parse_manifest_line(line)), expected, "{line:?}");
+        }
+    }
+
+    // Manifeste écrit avec --manifest-checksum, fin de ligne `line_end`
+    fn with_footer(body: &[u8], comment: &str, line_end: &[u8]) -> Vec<u8> {
+        let mut content = Vec::new();
+        let mut writer = ChecksumWriter {
+            out: Box::new(&mut content),
+            hasher: Some(Sha256::new()),
+        };
+        writer.write_all(body).unwrap();
+        writer.finish(comment, line_end).unwrap();
+        content
+    }
+
+    #[test]
+    fn manifest_checksum_footer() {
+        let manifest = Path::new("checksums.txt");
+        let body = b"# zhashgen\nabcd *a.txt\n".as_slice();
+        for content in [
+            with_footer(body, "#", b"\n"),
+            with_footer(b"a.txt 1A2B3C4D\r\n", ";", b"\r\n"),
+            with_footer(b"abcd *a.txt\0", "#", b"\0"),
+            // Sans pied de page : rien à vérifier
+            body.to_vec(),
+            Vec::new(),
+        ] {
+            assert!(verify_manifest_checksum(manifest, &content, Lang::En).is_ok());
+        }
+
+        // Une entrée modifiée ou un digest faux : rejet
+        let valid = with_footer(body, "#", b"\n");
+        let mut altered = valid.clone();
+        altered[13] = b'f';
+        let mut forged = valid;
+        let last = forged.len() - 2;
+        forged[last] = if forged[last] == b'0' { b'1' } else { b'0' };
+        for content in [altered, forged] {
+            let err = verify_manifest_checksum(manifest, &content, Lang::En).unwrap_err();
+            assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        }
+    }
+
+    #[test]
+    fn parse_sizes() {
+        for (input, expected) in [
+            ("4096", 4096),
+            ("0", 0),
+            ("12B", 12),
+            ("64KiB", 64 << 10),
+            ("64k", 64 << 10),
+            ("10MB", 10_000_000),
+            ("10 mb", 10_000_000),
+            ("1.5GiB", 3 << 29),
+            (" 2T ", 2 << 40),
+            ("1tb", 1_000_000_000_000),
+        ] {
+            assert_eq!(parse_size(input), Ok(expected), "{input}");
+        }
+        for input in ["", "KiB", "1.2.3M", "-1", "10 PB", "1e3"] {
+            assert!(parse_size(input).is_err(), "{input}");
+        }
+    }
+
+    #[test]
+    fn file_list_separators() {
+        let lines = parse_file_list(b"a.txt\r\n\nsous dossier/b.txt\nc.txt");
+        assert_eq!(
+            lines,
+            ["a.txt", "sous dossier/b.txt", "c.txt"].map(PathBuf::from)
+        );
+        // find -print0 : un nom peut contenir un saut de ligne
+        let nul = parse_file_list(b"a.txt\0ligne\nsuivante\0\0");
+        assert_eq!(nul, ["a.txt", "ligne\nsuivante"].map(PathBuf::from));
+        #[cfg(unix)]
+        {
+            use std::os::unix::ffi::OsStrExt;
+            let latin1 = parse_file_list(b"caf\xe9.txt\n");
+            assert_eq!(latin1[0].as_os_str().as_bytes(), b"caf\xe9.txt");
+        }
+    }
+
+    #[test]
+    fn reorder_buffer_emits_in_index_order() {
+        let mut emitted = Vec::new();
+        let mut reorder = ReorderBuffer::new();
+        for (index, item) in [(2, 'c'), (0, 'a'), (3, 'd'), (1, 'b'), (6, 'g'), (5, 'f')] {
+            reorder
+                .push(index, item, |item| {
+                    emitted.push(item);
+                    Ok(())
+                })
+                .unwrap();
+            // Préfixe contigu seulement : rien avant l'arrivée de l'indice 0
+            if index == 2 {
+                assert!(emitted.is_empty());
+            }
+        }
+        // L'indice 4 manque (interruption) : 5 et 6 attendent la fin
+        assert_eq!(emitted, ['a', 'b', 'c', 'd']);
+        reorder
+            .flush(|item| {
+                emitted.push(item);
+                Ok(())
+            })
+            .unwrap();
+        assert_eq!(emitted, ['a', 'b', 'c', 'd', 'f', 'g']);
+    }
+
+    #[test]
+    fn reorder_buffer_stops_at_first_error() {
+        let mut emitted = Vec::new();
+        let mut sink = |item: io::Result<char>| -> io::Result<()> {
+            emitted.push(item?);
+            Ok(())
+        };
+        let mut reorder = ReorderBuffer::new();
+        reorder
+            .push(1, Err(io::Error::other("échec")), &mut sink)
+            .unwrap();
+        reorder.push(2, Ok('c'), &mut sink).unwrap();
+        // L'erreur de l'indice 1 n'est propagée qu'à son tour, après l'indice 0
+        let err = reorder.push(0, Ok('a'), &mut sink).unwrap_err();
+        assert_eq!(err.to_string(), "échec");
+        assert_eq!(emitted, ['a']);
+    }
+
+    #[test]
+    fn state_round_trip() {
+        let dir = std::env::temp_dir().join(format!("zhashgen-state-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("checksums.txt.state");
+        let config = HashConfig::default();
+        let records = [
+            record("a.txt", 3, vec![0xAB; 32], vec![1, 2, 3, 4]),
+            record("sous dossier/b c.bin", 0, vec![0; 32], vec![0; 4]),
+        ];
+        save_state(&path, &ALGOS, &config, &records).unwrap();
+
+        let state = load_state(&path, &ALGOS, &config, Lang::En).unwrap();
+        assert_eq!(state.len(), 2);
+        let entry = &state["sous dossier/b c.bin"];
+        assert_eq!((entry.size, entry.digests.len()), (0, 2));
+        let entry = &state["a.txt"];
+        assert_eq!(entry.size, 3);
+        assert_eq!(entry.modified, 1_700_000_000_123_456_789);
+        assert_eq!(entry.digests, [vec![0xAB; 32], vec![1, 2, 3, 4]]);
+
+        // Autres algorithmes ou autres paramètres : état ignoré, tout est recalculé
+        assert!(
+            load_state(&path, &ALGOS[..1], &config, Lang::En)
+                .unwrap()
+                .is_empty()
+        );
+        let decompress = HashConfig {
+            decompress: true,
+            ..config
+        };
+        assert!(
+            load_state(&path, &ALGOS, &decompress, Lang::En)
+                .unwrap()
+                .is_empty()
+        );
+        // Absent : état vide, sans erreur
+        assert!(
+            load_state(&dir.join("absent"), &ALGOS, &config, Lang::En)
+                .unwrap()
+                .is_empty()
+        );
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn state_rejects_corrupted_lines() {
+        let dir = std::env::temp_dir().join(format!("zhashgen-corrupt-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("checksums.txt.state");
+        let config = HashConfig::default();
+        let header = state_header(&ALGOS, &config);
+        for line in [
+            "3 1700000000 abab",                // chemin manquant
+            "trois 1700000000 abab,01020304 a", // taille non numérique
+            "3 1700000000 abab a",              // un seul digest pour deux algorithmes
+            "3 1700000000 zz,01020304 a",       // hexadécimal invalide
+        ] {
+            fs::write(&path, format!("{header}\n{line}\n")).unwrap();
+            let Err(err) = load_state(&path, &ALGOS, &config, Lang::En) else {
+                panic!("ligne acceptée : {line}");
+            };
+            assert_eq!(err.kind(), io::ErrorKind::InvalidData, "{line}");
+        }
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
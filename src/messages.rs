@@ -0,0 +1,405 @@
+//! Textes affichés à l'utilisateur (--lang) : statistiques, rapport de doublons,
+//! avertissements, erreurs et journal --verbose sur stderr, mode interactif. Les formats
+//! machine (manifestes, rapports --check/--compare) ne sont pas traduits.
+
+use clap::ValueEnum;
+
+#[derive(Copy, Clone, PartialEq, Eq, ValueEnum)]
+pub enum Lang {
+    En,
+    Fr,
+}
+
+#[derive(Copy, Clone)]
+pub enum Msg {
+    // Bilan de fin de génération
+    Done,
+    Statistics,
+    FilesProcessed,
+    Errors,
+    EmptyFiles,
+    Skipped,
+    TotalSize,
+    Elapsed,
+    Throughput,
+    Duplicates,
+    PressEnter,
+    Interrupting,
+    Interrupted,
+    Watching,
+    // Rapport --find-dupes
+    Files,
+    Each,
+    Groups,
+    DuplicateGroups,
+    Reclaimable,
+    // --hardlink-dupes
+    HardlinksPlanned,
+    ConfirmHardlinks,
+    HardlinksPreviewOnly,
+    HardlinksCreated,
+    Freed,
+    // Avertissements sur stderr
+    Warning,
+    NonUtf8Path,
+    NonUnicodePath,
+    LockIgnored,
+    ExceedsDigest,
+    Characters,
+    KeptWhole,
+    Collisions,
+    WeakDigest,
+    More,
+    ChangedWhileRead,
+    BytesHashed,
+    NotLinked,
+    OtherFilesystem,
+    ContentDiffers,
+    NotReplacedByLink,
+    Error,
+    PanicWhileHashing,
+    CountedAsError,
+    // Erreurs
+    EmptyHmacKey,
+    NeedsTextFormat,
+    NeedsTextOrSfv,
+    NeedsCrc32Alone,
+    NeedsManifestFile,
+    NeedsStdout,
+    SingleColumn,
+    NeedsHexEncoding,
+    NeedsCryptoAlgo,
+    IncompatibleWith,
+    MustBeNonZero,
+    HmacManifest,
+    Locked,
+    ScanAborted,
+    MoreThan,
+    Under,
+    Limit,
+    NothingHashed,
+    InvalidIgnoreFile,
+    InvalidGlob,
+    InvalidTemplate,
+    UnclosedBrace,
+    StrayBrace,
+    UnknownTemplateField,
+    TemplateFields,
+    CorruptedState,
+    NoManifest,
+    TamperedManifest,
+    Expected,
+    Computed,
+    InvalidBase64,
+    NoAlgoForLength,
+    ShortenedManifest,
+    AmbiguousLength,
+    SpecifyAlgo,
+    Panic,
+    UnreadableLinkTarget,
+    ModifiedSinceComparison,
+    InvalidSize,
+    UnknownSizeUnit,
+    InvalidCrc32Value,
+    HexDigits,
+    InvalidBlake2bLength,
+    To,
+    Bytes,
+    ZeroBuffer,
+    BufferTooLarge,
+    // Journal --verbose
+    IoProfile,
+    AlreadyPresent,
+    SymbolicLink,
+    UnchangedReused,
+    SkippedFile,
+    OwnOutput,
+    IgnorePatternsRead,
+    AlreadyLinked,
+    FooterVerified,
+    Rehashed,
+    Removed,
+    // Mode interactif
+    NoteCastagnoli,
+    NoteCityMmap,
+    NoteDefault,
+    NoteMd5,
+    NoteNoCollisionResistance,
+    SourceDir,
+    AlgoChoice,
+    YourChoice,
+    ThreadCount,
+    InvalidThreads,
+    OutputDir,
+    Configuration,
+    Source,
+    Algorithm,
+    Threads,
+    Manifest,
+    Manifests,
+}
+
+impl Msg {
+    pub fn text(self, lang: Lang) -> &'static str {
+        match lang {
+            Lang::En => self.en(),
+            Lang::Fr => self.fr(),
+        }
+    }
+
+    fn en(self) -> &'static str {
+        match self {
+            Msg::Done => "Done! Hashes saved to:",
+            Msg::Statistics => "=== Statistics ===",
+            Msg::FilesProcessed => "Files processed",
+            Msg::Errors => "Errors",
+            Msg::EmptyFiles => "Empty files",
+            Msg::Skipped => "skipped",
+            Msg::TotalSize => "Total size",
+            Msg::Elapsed => "Elapsed time",
+            Msg::Throughput => "Average throughput",
+            Msg::Duplicates => "Duplicates",
+            Msg::PressEnter => "Press Enter to exit...",
+            Msg::Interrupting => "Stopping after files in progress (Ctrl-C again to quit now)...",
+            Msg::Interrupted => "Aborted:",
+            Msg::Watching => "Watching for changes (Ctrl-C to stop):",
+            Msg::Files => "files",
+            Msg::Each => "each",
+            Msg::Groups => "groups",
+            Msg::DuplicateGroups => "duplicate groups",
+            Msg::Reclaimable => "reclaimable",
+            Msg::HardlinksPlanned => "hard links to create",
+            Msg::ConfirmHardlinks => "Replace these duplicates with hard links? [y/N]",
+            Msg::HardlinksPreviewOnly => "Preview only: nothing was modified (--yes to apply)",
+            Msg::HardlinksCreated => "hard links created",
+            Msg::Freed => "freed",
+            Msg::Warning => "Warning:",
+            Msg::NonUtf8Path => "non-UTF-8 path recorded approximately:",
+            Msg::NonUnicodePath => "non-Unicode path recorded approximately:",
+            Msg::LockIgnored => "ignoring lock (--force):",
+            Msg::ExceedsDigest => "exceeds the digest",
+            Msg::Characters => "characters",
+            Msg::KeptWhole => "kept whole",
+            Msg::Collisions => "digest collision(s) between files of different sizes among",
+            Msg::WeakDigest => "; algorithm or --truncate too weak?",
+            Msg::More => "more",
+            Msg::ChangedWhileRead => "changed size while being read",
+            Msg::BytesHashed => "bytes hashed",
+            Msg::NotLinked => "not linked:",
+            Msg::OtherFilesystem => "on a different file system from",
+            Msg::ContentDiffers => "same digest but different content from",
+            Msg::NotReplacedByLink => "not replaced by a link:",
+            Msg::Error => "Error:",
+            Msg::PanicWhileHashing => "panic while hashing",
+            Msg::CountedAsError => "file counted as an error",
+            Msg::EmptyHmacKey => "Empty HMAC key",
+            Msg::NeedsTextFormat => "requires --format text",
+            Msg::NeedsTextOrSfv => "requires --format text or sfv",
+            Msg::NeedsCrc32Alone => "requires --algo crc32 (alone)",
+            Msg::NeedsManifestFile => "requires a manifest on disk (not stdout)",
+            Msg::NeedsStdout => "requires stdout output (--output -)",
+            Msg::SingleColumn => "accepts neither --with-meta nor several --algo",
+            Msg::NeedsHexEncoding => "requires --encoding hex",
+            Msg::NeedsCryptoAlgo => {
+                "requires a cryptographic algorithm (md5, sha1, sha256, sha512, sha384, sha512-256, sha3-256, sha3-512, blake2b, blake2s), not"
+            }
+            Msg::IncompatibleWith => "is incompatible with",
+            Msg::MustBeNonZero => "must be non-zero",
+            Msg::HmacManifest => "HMAC manifest: specify --hmac-key or --hmac-key-file",
+            Msg::Locked => {
+                "is locked: another zhashgen is already writing this manifest (--force to override)"
+            }
+            Msg::ScanAborted => "Scan aborted:",
+            Msg::MoreThan => "more than",
+            Msg::Under => "under",
+            Msg::Limit => "limit",
+            Msg::NothingHashed => "no file hashed",
+            Msg::InvalidIgnoreFile => "Invalid ignore file",
+            Msg::InvalidGlob => "Invalid glob pattern",
+            Msg::InvalidTemplate => "Invalid template:",
+            Msg::UnclosedBrace => "unclosed { in",
+            Msg::StrayBrace => "stray } in",
+            Msg::UnknownTemplateField => "Unknown template field:",
+            Msg::TemplateFields => "expected: digest, path, size, algo, mtime",
+            Msg::CorruptedState => "Corrupted state file:",
+            Msg::NoManifest => "No manifest",
+            Msg::TamperedManifest => "Tampered manifest:",
+            Msg::Expected => "expected",
+            Msg::Computed => "computed",
+            Msg::InvalidBase64 => "Invalid base64 digest:",
+            Msg::NoAlgoForLength => "No supported algorithm produces a digest of",
+            Msg::ShortenedManifest => "shortened manifest: specify --truncate",
+            Msg::AmbiguousLength => "Ambiguous digest length:",
+            Msg::SpecifyAlgo => "specify --algo",
+            Msg::Panic => "panic:",
+            Msg::UnreadableLinkTarget => "unreadable symlink target",
+            Msg::ModifiedSinceComparison => "modified since the comparison",
+            Msg::InvalidSize => "Invalid size:",
+            Msg::UnknownSizeUnit => "Unknown size unit:",
+            Msg::InvalidCrc32Value => "Invalid CRC32 value:",
+            Msg::HexDigits => "1 to 8 hex digits",
+            Msg::InvalidBlake2bLength => "Invalid BLAKE2b length:",
+            Msg::To => "to",
+            Msg::Bytes => "bytes",
+            Msg::ZeroBuffer => "The buffer size must be non-zero",
+            Msg::BufferTooLarge => "Buffer too large:",
+            Msg::IoProfile => "I/O profile:",
+            Msg::AlreadyPresent => "file(s) already present",
+            Msg::SymbolicLink => "symbolic link",
+            Msg::UnchangedReused => "Unchanged, digest reused:",
+            Msg::SkippedFile => "Skipped:",
+            Msg::OwnOutput => "file written by zhashgen",
+            Msg::IgnorePatternsRead => "exclusion pattern(s) read from",
+            Msg::AlreadyLinked => "already linked to",
+            Msg::FooterVerified => "footer verified",
+            Msg::Rehashed => "rehashed",
+            Msg::Removed => "removed",
+            Msg::NoteCastagnoli => "Castagnoli",
+            Msg::NoteCityMmap => "large files read via mmap",
+            Msg::NoteDefault => "default",
+            Msg::NoteMd5 => "obsolete, compatibility only",
+            Msg::NoteNoCollisionResistance => "hash tables only, not collision-resistant",
+            Msg::SourceDir => "Source directory",
+            Msg::AlgoChoice => "Choose the algorithm:",
+            Msg::YourChoice => "Your choice",
+            Msg::ThreadCount => "Number of threads",
+            Msg::InvalidThreads => "Invalid number of threads",
+            Msg::OutputDir => "Output directory",
+            Msg::Configuration => "Configuration:",
+            Msg::Source => "Source",
+            Msg::Algorithm => "Algorithm",
+            Msg::Threads => "Threads",
+            Msg::Manifest => "Manifest",
+            Msg::Manifests => "manifests",
+        }
+    }
+
+    fn fr(self) -> &'static str {
+        match self {
+            Msg::Done => "Terminé ! Hashes enregistrés dans :",
+            Msg::Statistics => "=== Statistiques ===",
+            Msg::FilesProcessed => "Fichiers traités",
+            Msg::Errors => "Erreurs",
+            Msg::EmptyFiles => "Fichiers vides",
+            Msg::Skipped => "écartés",
+            Msg::TotalSize => "Volume total",
+            Msg::Elapsed => "Temps écoulé",
+            Msg::Throughput => "Débit moyen",
+            Msg::Duplicates => "Doublons",
+            Msg::PressEnter => "Appuyez sur Entrée pour quitter...",
+            Msg::Interrupting => "Arrêt après les fichiers en cours (Ctrl-C pour forcer)...",
+            Msg::Interrupted => "Interrompu :",
+            Msg::Watching => "Surveillance des modifications (Ctrl-C pour arrêter) :",
+            Msg::Files => "fichiers",
+            Msg::Each => "chacun",
+            Msg::Groups => "groupes",
+            Msg::DuplicateGroups => "groupes de doublons",
+            Msg::Reclaimable => "récupérables",
+            Msg::HardlinksPlanned => "liens physiques à créer",
+            Msg::ConfirmHardlinks => "Remplacer ces doublons par des liens physiques ? [o/N]",
+            Msg::HardlinksPreviewOnly => {
+                "Aperçu seulement : rien n'a été modifié (--yes pour appliquer)"
+            }
+            Msg::HardlinksCreated => "liens physiques créés",
+            Msg::Freed => "libérés",
+            Msg::Warning => "Avertissement :",
+            Msg::NonUtf8Path => "chemin non UTF-8 enregistré de façon approchée :",
+            Msg::NonUnicodePath => "chemin non Unicode enregistré de façon approchée :",
+            Msg::LockIgnored => "verrou ignoré (--force) :",
+            Msg::ExceedsDigest => "dépasse le digest",
+            Msg::Characters => "caractères",
+            Msg::KeptWhole => "conservé entier",
+            Msg::Collisions => "collision(s) de digest entre fichiers de tailles différentes sur",
+            Msg::WeakDigest => " ; algorithme ou --truncate trop faible ?",
+            Msg::More => "de plus",
+            Msg::ChangedWhileRead => "a changé de taille pendant la lecture",
+            Msg::BytesHashed => "octets hachés",
+            Msg::NotLinked => "non lié :",
+            Msg::OtherFilesystem => "sur un autre système de fichiers que",
+            Msg::ContentDiffers => "même digest mais contenu différent de",
+            Msg::NotReplacedByLink => "non remplacé par un lien :",
+            Msg::Error => "Erreur :",
+            Msg::PanicWhileHashing => "panique pendant le hachage de",
+            Msg::CountedAsError => "fichier compté en erreur",
+            Msg::EmptyHmacKey => "Clé HMAC vide",
+            Msg::NeedsTextFormat => "nécessite --format text",
+            Msg::NeedsTextOrSfv => "nécessite --format text ou sfv",
+            Msg::NeedsCrc32Alone => "nécessite --algo crc32 (seul)",
+            Msg::NeedsManifestFile => "nécessite un manifeste sur disque (pas de sortie stdout)",
+            Msg::NeedsStdout => "nécessite une sortie stdout (--output -)",
+            Msg::SingleColumn => "n'accepte ni --with-meta ni plusieurs --algo",
+            Msg::NeedsHexEncoding => "nécessite --encoding hex",
+            Msg::NeedsCryptoAlgo => {
+                "nécessite un algorithme cryptographique (md5, sha1, sha256, sha512, sha384, sha512-256, sha3-256, sha3-512, blake2b, blake2s), pas"
+            }
+            Msg::IncompatibleWith => "est incompatible avec",
+            Msg::MustBeNonZero => "doit être non nul",
+            Msg::HmacManifest => "Manifeste HMAC : précisez --hmac-key ou --hmac-key-file",
+            Msg::Locked => {
+                "est verrouillé : un autre zhashgen écrit déjà ce manifeste (--force pour passer outre)"
+            }
+            Msg::ScanAborted => "Parcours interrompu :",
+            Msg::MoreThan => "plus de",
+            Msg::Under => "sous",
+            Msg::Limit => "limite",
+            Msg::NothingHashed => "aucun fichier haché",
+            Msg::InvalidIgnoreFile => "Fichier d'exclusion invalide",
+            Msg::InvalidGlob => "Motif glob invalide",
+            Msg::InvalidTemplate => "Modèle invalide :",
+            Msg::UnclosedBrace => "{ non fermé dans",
+            Msg::StrayBrace => "} isolé dans",
+            Msg::UnknownTemplateField => "Champ de modèle inconnu :",
+            Msg::TemplateFields => "attendus : digest, path, size, algo, mtime",
+            Msg::CorruptedState => "Fichier d'état corrompu :",
+            Msg::NoManifest => "Aucun manifeste",
+            Msg::TamperedManifest => "Manifeste altéré :",
+            Msg::Expected => "attendu",
+            Msg::Computed => "calculé",
+            Msg::InvalidBase64 => "Digest base64 invalide :",
+            Msg::NoAlgoForLength => "Aucun algorithme supporté ne produit un digest de",
+            Msg::ShortenedManifest => "manifeste raccourci : précisez --truncate",
+            Msg::AmbiguousLength => "Longueur de digest ambiguë :",
+            Msg::SpecifyAlgo => "précisez --algo",
+            Msg::Panic => "panique :",
+            Msg::UnreadableLinkTarget => "cible du lien symbolique illisible",
+            Msg::ModifiedSinceComparison => "modifié depuis la comparaison",
+            Msg::InvalidSize => "Taille invalide :",
+            Msg::UnknownSizeUnit => "Unité de taille inconnue :",
+            Msg::InvalidCrc32Value => "Valeur CRC32 invalide :",
+            Msg::HexDigits => "1 à 8 chiffres hexadécimaux",
+            Msg::InvalidBlake2bLength => "Longueur BLAKE2b invalide :",
+            Msg::To => "à",
+            Msg::Bytes => "octets",
+            Msg::ZeroBuffer => "La taille du tampon doit être non nulle",
+            Msg::BufferTooLarge => "Tampon trop grand :",
+            Msg::IoProfile => "Profil d'E/S :",
+            Msg::AlreadyPresent => "fichier(s) déjà présent(s)",
+            Msg::SymbolicLink => "lien symbolique",
+            Msg::UnchangedReused => "Inchangé, digest repris :",
+            Msg::SkippedFile => "Ignoré :",
+            Msg::OwnOutput => "fichier produit par zhashgen",
+            Msg::IgnorePatternsRead => "motif(s) d'exclusion lus dans",
+            Msg::AlreadyLinked => "déjà lié à",
+            Msg::FooterVerified => "pied de page vérifié",
+            Msg::Rehashed => "re-haché(s)",
+            Msg::Removed => "retiré(s)",
+            Msg::NoteCastagnoli => "Castagnoli",
+            Msg::NoteCityMmap => "gros fichiers lus via mmap",
+            Msg::NoteDefault => "défaut",
+            Msg::NoteMd5 => "obsolète, compatibilité uniquement",
+            Msg::NoteNoCollisionResistance => "tables de hachage, sans résistance aux collisions",
+            Msg::SourceDir => "Répertoire source",
+            Msg::AlgoChoice => "Choix de l'algorithme :",
+            Msg::YourChoice => "Votre choix",
+            Msg::ThreadCount => "Nombre de threads",
+            Msg::InvalidThreads => "Nombre de threads invalide",
+            Msg::OutputDir => "Répertoire de sortie",
+            Msg::Configuration => "Configuration :",
+            Msg::Source => "Source",
+            Msg::Algorithm => "Algorithme",
+            Msg::Threads => "Threads",
+            Msg::Manifest => "Manifeste",
+            Msg::Manifests => "manifestes",
+        }
+    }
+}
@@ -0,0 +1,107 @@
+//! MurmurHash3 x64 128 bits (Austin Appleby, domaine public), en version incrémentale.
+//! Aucun crate maintenu ne propose de streaming : fasthash est abandonné et murmur3
+//! exige un `Read`. Le résultat est identique à `MurmurHash3_x64_128` de SMHasher.
+
+const C1: u64 = 0x87c3_7b91_1142_53d5;
+const C2: u64 = 0x4cf5_ad43_2745_937f;
+
+pub struct Murmur3 {
+    h1: u64,
+    h2: u64,
+    // Fin de bloc en attente (moins de 16 octets)
+    tail: [u8; 16],
+    tail_len: usize,
+    total: u64,
+}
+
+impl Murmur3 {
+    pub fn with_seed(seed: u32) -> Self {
+        Murmur3 {
+            h1: seed as u64,
+            h2: seed as u64,
+            tail: [0; 16],
+            tail_len: 0,
+            total: 0,
+        }
+    }
+
+    pub fn update(&mut self, mut data: &[u8]) {
+        self.total += data.len() as u64;
+        if self.tail_len > 0 {
+            let n = data.len().min(16 - self.tail_len);
+            self.tail[self.tail_len..self.tail_len + n].copy_from_slice(&data[..n]);
+            self.tail_len += n;
+            data = &data[n..];
+            if self.tail_len < 16 {
+                return;
+            }
+            let block = self.tail;
+            self.block(&block);
+            self.tail_len = 0;
+        }
+        let mut blocks = data.chunks_exact(16);
+        for block in &mut blocks {
+            self.block(block.try_into().unwrap());
+        }
+        let rest = blocks.remainder();
+        self.tail[..rest.len()].copy_from_slice(rest);
+        self.tail_len = rest.len();
+    }
+
+    fn block(&mut self, block: &[u8; 16]) {
+        let k1 = u64::from_le_bytes(block[..8].try_into().unwrap());
+        let k2 = u64::from_le_bytes(block[8..].try_into().unwrap());
+
+        self.h1 ^= mix_k1(k1);
+        self.h1 = self.h1.rotate_left(27).wrapping_add(self.h2);
+        self.h1 = self.h1.wrapping_mul(5).wrapping_add(0x52dc_e729);
+
+        self.h2 ^= mix_k2(k2);
+        self.h2 = self.h2.rotate_left(31).wrapping_add(self.h1);
+        self.h2 = self.h2.wrapping_mul(5).wrapping_add(0x3849_5ab5);
+    }
+
+    /// Digest de 16 octets : h1 puis h2, en petit-boutiste comme la sortie de référence
+    pub fn finalize(&self) -> [u8; 16] {
+        let (mut h1, mut h2) = (self.h1, self.h2);
+        // Octets restants complétés de zéros, sans la rotation de fin de bloc
+        let mut tail = [0; 16];
+        tail[..self.tail_len].copy_from_slice(&self.tail[..self.tail_len]);
+        if self.tail_len > 8 {
+            h2 ^= mix_k2(u64::from_le_bytes(tail[8..].try_into().unwrap()));
+        }
+        if self.tail_len > 0 {
+            h1 ^= mix_k1(u64::from_le_bytes(tail[..8].try_into().unwrap()));
+        }
+
+        h1 ^= self.total;
+        h2 ^= self.total;
+        h1 = h1.wrapping_add(h2);
+        h2 = h2.wrapping_add(h1);
+        h1 = fmix(h1);
+        h2 = fmix(h2);
+        h1 = h1.wrapping_add(h2);
+        h2 = h2.wrapping_add(h1);
+
+        let mut out = [0; 16];
+        out[..8].copy_from_slice(&h1.to_le_bytes());
+        out[8..].copy_from_slice(&h2.to_le_bytes());
+        out
+    }
+}
+
+fn mix_k1(k1: u64) -> u64 {
+    k1.wrapping_mul(C1).rotate_left(31).wrapping_mul(C2)
+}
+
+fn mix_k2(k2: u64) -> u64 {
+    k2.wrapping_mul(C2).rotate_left(33).wrapping_mul(C1)
+}
+
+fn fmix(mut k: u64) -> u64 {
+    k ^= k >> 33;
+    k = k.wrapping_mul(0xff51_afd7_ed55_8ccd);
+    k ^= k >> 33;
+    k = k.wrapping_mul(0xc4ce_b9fe_1a85_ec53);
+    k ^ (k >> 33)
+}